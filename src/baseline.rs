@@ -0,0 +1,241 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::parser::PackageValue;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub duplicates: BTreeMap<String, BTreeSet<String>>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BaselineDiff {
+    pub added: Vec<String>,
+    pub resolved: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct VersionChange {
+    pub name: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl Baseline {
+    pub fn new(duplicates: &HashMap<String, Vec<PackageValue>>) -> Self {
+        Baseline {
+            duplicates: duplicates
+                .iter()
+                .map(|(name, values)| {
+                    let versions = values.iter().map(|v| v.version.clone()).collect();
+                    (name.clone(), versions)
+                })
+                .collect(),
+        }
+    }
+
+    pub fn diff(&self, current: &Baseline) -> BaselineDiff {
+        BaselineDiff {
+            added: current
+                .duplicates
+                .keys()
+                .filter(|name| !self.duplicates.contains_key(*name))
+                .cloned()
+                .collect(),
+            resolved: self
+                .duplicates
+                .keys()
+                .filter(|name| !current.duplicates.contains_key(*name))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    // A package missing on one side counts as having no versions there
+    pub fn diff_versions(&self, current: &Baseline) -> Vec<VersionChange> {
+        let empty = BTreeSet::new();
+        let names: BTreeSet<&String> = self
+            .duplicates
+            .keys()
+            .chain(current.duplicates.keys())
+            .collect();
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let previous = self.duplicates.get(name).unwrap_or(&empty);
+                let versions = current.duplicates.get(name).unwrap_or(&empty);
+                let change = VersionChange {
+                    name: name.clone(),
+                    added: versions.difference(previous).cloned().collect(),
+                    removed: previous.difference(versions).cloned().collect(),
+                };
+                (!change.added.is_empty() || !change.removed.is_empty()).then_some(change)
+            })
+            .collect()
+    }
+}
+
+pub fn write_baseline(path: &str, baseline: &Baseline) {
+    let content = serde_json::to_string_pretty(baseline).unwrap();
+    fs::write(path, content).unwrap_or_else(|_| {
+        panic!("Failed to write the baseline: {}", path);
+    });
+}
+
+pub fn read_baseline(path: &str) -> Baseline {
+    let content = fs::read_to_string(path).unwrap_or_else(|_| {
+        panic!("Failed to read the baseline: {}", path);
+    });
+    serde_json::from_str(&content).unwrap_or_else(|_| {
+        panic!("Failed to parse the baseline: {}", path);
+    })
+}
+
+#[cfg(feature = "remote-baseline")]
+pub fn fetch_baseline(url: &str) -> Baseline {
+    let response = ureq::get(url).call().unwrap_or_else(|e| {
+        panic!("Failed to fetch the baseline: {}: {}", url, e);
+    });
+    let content = response.into_string().unwrap_or_else(|_| {
+        panic!("Failed to read the baseline: {}", url);
+    });
+    serde_json::from_str(&content).unwrap_or_else(|_| {
+        panic!("Failed to parse the baseline: {}", url);
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duplicates(names: &[&str]) -> HashMap<String, Vec<PackageValue>> {
+        names
+            .iter()
+            .map(|name| {
+                let values = vec![
+                    PackageValue::new(name, "2.0.0", "./src/1"),
+                    PackageValue::new(name, "1.0.0", "./src/2"),
+                ];
+                (name.to_string(), values)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_should_report_added_duplicates() {
+        let baseline = Baseline::new(&duplicates(&["lodash"]));
+        let current = Baseline::new(&duplicates(&["lodash", "react"]));
+
+        let diff = baseline.diff(&current);
+
+        assert_eq!(diff.added, vec!["react".to_string()]);
+        assert!(diff.resolved.is_empty());
+    }
+
+    #[test]
+    fn it_should_report_resolved_duplicates() {
+        let baseline = Baseline::new(&duplicates(&["lodash", "react"]));
+        let current = Baseline::new(&duplicates(&["react"]));
+
+        let diff = baseline.diff(&current);
+
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.resolved, vec!["lodash".to_string()]);
+    }
+
+    #[test]
+    fn it_should_skip_unchanged_duplicates() {
+        let baseline = Baseline::new(&duplicates(&["lodash", "react"]));
+        let current = Baseline::new(&duplicates(&["react", "lodash"]));
+
+        let diff = baseline.diff(&current);
+
+        assert_eq!(
+            diff,
+            BaselineDiff {
+                added: vec![],
+                resolved: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn it_should_diff_version_sets() {
+        let previous = Baseline::new(&duplicates(&["lodash", "react"]));
+        let mut current = Baseline::new(&duplicates(&["lodash", "vue"]));
+        current
+            .duplicates
+            .get_mut("lodash")
+            .unwrap()
+            .insert("3.0.0".to_string());
+
+        let changes = previous.diff_versions(&current);
+
+        assert_eq!(
+            changes,
+            vec![
+                VersionChange {
+                    name: "lodash".to_string(),
+                    added: vec!["3.0.0".to_string()],
+                    removed: vec![],
+                },
+                VersionChange {
+                    name: "react".to_string(),
+                    added: vec![],
+                    removed: vec!["1.0.0".to_string(), "2.0.0".to_string()],
+                },
+                VersionChange {
+                    name: "vue".to_string(),
+                    added: vec!["1.0.0".to_string(), "2.0.0".to_string()],
+                    removed: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_round_trip_baseline_file() {
+        let path = std::env::temp_dir().join("nodedup-baseline-test.json");
+        let path = path.to_str().unwrap();
+        let baseline = Baseline::new(&duplicates(&["lodash"]));
+
+        write_baseline(path, &baseline);
+
+        assert_eq!(read_baseline(path), baseline);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(feature = "remote-baseline")]
+    #[test]
+    fn it_should_fetch_baseline_over_http() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        let baseline = Baseline::new(&duplicates(&["lodash"]));
+        let body = serde_json::to_string(&baseline).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/baseline.json", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            BufReader::new(&stream)
+                .lines()
+                .map_while(Result::ok)
+                .take_while(|line| !line.is_empty())
+                .for_each(drop);
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+        });
+
+        assert_eq!(fetch_baseline(&url), baseline);
+        server.join().unwrap();
+    }
+}
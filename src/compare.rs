@@ -0,0 +1,124 @@
+use std::{path::PathBuf, process::Command};
+
+use crate::{
+    baseline::{Baseline, VersionChange},
+    diagnostics::Counters,
+    parser::{self, is_ignored},
+};
+
+pub fn parse_range(range: &str) -> Result<(&str, &str), String> {
+    match range.split_once("..") {
+        Some((from, to)) if !from.is_empty() && !to.is_empty() => Ok((from, to)),
+        _ => Err(format!(
+            "Failed to parse the compare range: {}, expected <ref1>..<ref2>",
+            range
+        )),
+    }
+}
+
+// Paths printed by ls-tree are relative to the folder git runs in, which is what `git show`
+// expects after `./`
+pub fn read_contents_at(dir_path: &str, git_ref: &str) -> Vec<(PathBuf, String)> {
+    let listed = run_git(dir_path, &["ls-tree", "-r", "--name-only", git_ref, "."]);
+    listed
+        .lines()
+        .filter(|line| line.rsplit('/').next() == Some("package.json"))
+        .filter(|line| !line.contains("node_modules/"))
+        .map(|line| {
+            let content = run_git(dir_path, &["show", &format!("{}:./{}", git_ref, line)]);
+            (PathBuf::from(dir_path).join(line), content)
+        })
+        .collect()
+}
+
+fn run_git(dir_path: &str, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir_path)
+        .args(args)
+        .output()
+        .unwrap_or_else(|e| panic!("Failed to run git: {}", e));
+    if !output.status.success() {
+        panic!(
+            "Failed to run git {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+pub fn compare_contents(
+    before: &[(PathBuf, String)],
+    after: &[(PathBuf, String)],
+    ignores: &[String],
+    fields: &[String],
+) -> Vec<VersionChange> {
+    let versions = |contents: &[(PathBuf, String)]| {
+        let mut dependencies =
+            parser::collect_dependencies_from_contents(contents, fields, &mut Counters::default());
+        dependencies.retain(|name, _| !is_ignored(name, ignores));
+        Baseline::new(&dependencies)
+    };
+
+    versions(before).diff_versions(&versions(after))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(path: &str, content: &str) -> (PathBuf, String) {
+        (PathBuf::from(path), content.to_string())
+    }
+
+    #[test]
+    fn it_should_parse_range() {
+        assert_eq!(parse_range("main..feature"), Ok(("main", "feature")));
+        assert!(parse_range("main").is_err());
+        assert!(parse_range("main..").is_err());
+    }
+
+    #[test]
+    fn it_should_compare_versions_between_refs() {
+        let before = vec![
+            contents(
+                "./a/package.json",
+                r#"{"dependencies": {"lodash": "^4.17.0", "react": "^17.0.0"}}"#,
+            ),
+            contents(
+                "./b/package.json",
+                r#"{"dependencies": {"lodash": "^4.17.0", "left-pad": "^1.0.0"}}"#,
+            ),
+        ];
+        let after = vec![
+            contents(
+                "./a/package.json",
+                r#"{"dependencies": {"lodash": "^4.17.21", "react": "^17.0.0"}}"#,
+            ),
+            contents(
+                "./b/package.json",
+                r#"{"dependencies": {"lodash": "^4.17.0", "vue": "^3.0.0"}}"#,
+            ),
+        ];
+
+        let changes = compare_contents(&before, &after, &["vue".to_string()], &[]);
+
+        assert_eq!(
+            changes,
+            vec![
+                VersionChange {
+                    name: "left-pad".to_string(),
+                    added: vec![],
+                    removed: vec!["1.0.0".to_string()],
+                },
+                VersionChange {
+                    name: "lodash".to_string(),
+                    added: vec!["4.17.21".to_string()],
+                    removed: vec![],
+                },
+            ]
+        );
+    }
+}
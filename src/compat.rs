@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use semver::{Version, VersionReq};
+
+use crate::parser::{get_versions, normalize_version, PackageValue};
+
+pub fn retain_conflicting(duplicates: &mut HashMap<String, Vec<PackageValue>>) {
+    duplicates.retain(|_, values| !is_compatible(values));
+}
+
+// The lower bound of one of the ranges is the smallest version that can satisfy all of them,
+// so those are the only candidates worth checking.
+fn is_compatible(values: &[PackageValue]) -> bool {
+    let Some(requirements) = values
+        .iter()
+        .map(|v| to_version_req(&normalize_version(&v.range)))
+        .collect::<Option<Vec<VersionReq>>>()
+    else {
+        return false;
+    };
+
+    values
+        .iter()
+        .map(|v| {
+            let (major, minor, patch) = get_versions(&v.version);
+            Version::new(major, minor, patch)
+        })
+        .any(|candidate| requirements.iter().all(|r| r.matches(&candidate)))
+}
+
+pub fn to_version_req(range: &str) -> Option<VersionReq> {
+    if range.contains("||") {
+        return None;
+    }
+
+    // npm separates comparators with spaces and reads a bare version as an exact match, while
+    // the semver crate expects commas and reads it as a caret range
+    let comparators: Vec<String> = range
+        .split_whitespace()
+        .map(|comparator| {
+            if comparator.starts_with(|c: char| c.is_ascii_digit()) {
+                format!("={}", comparator)
+            } else {
+                comparator.to_string()
+            }
+        })
+        .collect();
+    VersionReq::parse(&comparators.join(", ")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(ranges: &[&str]) -> Vec<PackageValue> {
+        ranges
+            .iter()
+            .enumerate()
+            .map(|(i, range)| {
+                let version: String = range
+                    .chars()
+                    .filter(|c| *c == '.' || c.is_ascii_digit())
+                    .collect();
+                PackageValue::new("test", &version, i.to_string()).with_range(range)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_should_treat_overlapping_caret_ranges_as_compatible() {
+        assert!(is_compatible(&group(&["^1.2.0", "^1.3.0"])));
+        assert!(is_compatible(&group(&["~1.2.0", "^1.2.5"])));
+    }
+
+    #[test]
+    fn it_should_flag_disjoint_ranges() {
+        assert!(!is_compatible(&group(&["^1.0.0", "^2.0.0"])));
+        assert!(!is_compatible(&group(&["~1.2.0", "~1.3.0"])));
+        assert!(!is_compatible(&group(&["1.2.0", "1.3.0"])));
+        assert!(!is_compatible(&group(&["1.2.0", "^1.3.0"])));
+    }
+
+    #[test]
+    fn it_should_treat_exact_version_inside_range_as_compatible() {
+        assert!(is_compatible(&group(&["1.3.2", "^1.3.0"])));
+        assert!(is_compatible(&group(&["1.3", "1.3.4"])));
+    }
+
+    #[test]
+    fn it_should_support_space_separated_comparators() {
+        assert!(is_compatible(&group(&[">=1.0.0 <2.0.0", "^1.4.0"])));
+    }
+
+    #[test]
+    fn it_should_read_v_prefixed_ranges() {
+        assert!(is_compatible(&group(&["v1.3.2", ">=v1.0.0 <v2.0.0"])));
+        assert!(!is_compatible(&group(&["^v1.0.0", "^V2.0.0"])));
+    }
+
+    #[test]
+    fn it_should_flag_unparsable_ranges() {
+        assert!(!is_compatible(&group(&["^1.0.0", "1.0.0 || 2.0.0"])));
+    }
+
+    #[test]
+    fn it_should_retain_only_conflicting_groups() {
+        let mut duplicates: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        duplicates.insert("compatible".to_string(), group(&["^1.2.0", "^1.3.0"]));
+        duplicates.insert("conflicting".to_string(), group(&["^1.0.0", "^2.0.0"]));
+
+        retain_conflicting(&mut duplicates);
+
+        assert_eq!(duplicates.keys().collect::<Vec<_>>(), vec!["conflicting"]);
+    }
+}
@@ -0,0 +1,171 @@
+use std::{collections::HashMap, path::Path};
+
+use serde_json::Value;
+
+use crate::parser::{process_dependency, PackageValue, Section};
+
+const MANIFEST_NAMES: [&str; 2] = ["deno.json", "deno.jsonc"];
+
+pub fn is_deno_manifest(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| MANIFEST_NAMES.iter().any(|n| name == *n))
+}
+
+pub fn is_jsonc(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "jsonc")
+}
+
+// Only `npm:` and `jsr:` specifiers carry a version, URL imports and local paths are left out
+pub fn traverse_imports(
+    imports: Option<&Value>,
+    map: &mut HashMap<String, Vec<PackageValue>>,
+    path: &Path,
+) {
+    imports
+        .and_then(|i| i.as_object())
+        .into_iter()
+        .flat_map(|imports| imports.values())
+        .filter_map(|value| parse_specifier(value.as_str()?))
+        .for_each(|(name, range)| {
+            process_dependency(name, range, map, path, Section::Dependencies)
+        });
+}
+
+// `npm:@scope/name@^1.0.0/sub/path` is read as `@scope/name` and `^1.0.0`
+fn parse_specifier(specifier: &str) -> Option<(&str, &str)> {
+    let package = specifier
+        .strip_prefix("npm:")
+        .or_else(|| specifier.strip_prefix("jsr:"))?
+        .trim_start_matches('/');
+    // The scope's `@` is part of the name, the version starts at the next one
+    let at = package.get(1..)?.find('@')? + 1;
+    let name = &package[..at];
+    let range = package[at + 1..].split('/').next()?;
+    if range.is_empty() {
+        return None;
+    }
+
+    Some((name, range))
+}
+
+// deno.jsonc allows comments and trailing commas, which serde_json rejects
+pub fn strip_jsonc(content: &str) -> String {
+    remove_trailing_commas(&remove_comments(content))
+}
+
+fn remove_comments(content: &str) -> String {
+    let mut stripped = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            stripped.push(c);
+            match c {
+                '\\' => stripped.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                stripped.push(c);
+            }
+            ('/', Some('/')) => {
+                // The newline is kept so error positions still point at the right line
+                while chars.next_if(|c| *c != '\n').is_some() {}
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => stripped.push(c),
+        }
+    }
+
+    stripped
+}
+
+fn remove_trailing_commas(content: &str) -> String {
+    let mut stripped = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in content.char_indices() {
+        if in_string {
+            in_string = escaped || c != '"';
+            escaped = !escaped && c == '\\';
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' && content[i + 1..].trim_start().starts_with(['}', ']']) {
+            continue;
+        }
+        stripped.push(c);
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Counters;
+
+    #[test]
+    fn it_should_parse_specifiers() {
+        assert_eq!(
+            parse_specifier("npm:lodash@4.17.21"),
+            Some(("lodash", "4.17.21"))
+        );
+        assert_eq!(
+            parse_specifier("jsr:@std/assert@^1.0.0/equals"),
+            Some(("@std/assert", "^1.0.0"))
+        );
+        assert_eq!(parse_specifier("npm:lodash"), None);
+        assert_eq!(parse_specifier("npm:"), None);
+        assert_eq!(parse_specifier("jsr:@std/assert"), None);
+        assert_eq!(parse_specifier("https://esm.sh/preact@10.5.13"), None);
+        assert_eq!(parse_specifier("./src/utils.ts"), None);
+    }
+
+    #[test]
+    fn it_should_strip_jsonc_comments_and_trailing_commas() {
+        let content = "{\n  // imports\n  \"imports\": {\n    \"a\": \"npm:a@1 // not a comment\", /* b */\n    \"b\": \"npm:b@2,}\",\n  },\n}\n";
+
+        let value: Value = serde_json::from_str(&strip_jsonc(content)).unwrap();
+
+        assert_eq!(value["imports"]["a"], "npm:a@1 // not a comment");
+        assert_eq!(value["imports"]["b"], "npm:b@2,}");
+    }
+
+    #[test]
+    fn it_should_find_conflicting_deno_imports() {
+        let files = crate::lookup::get_package_json_files(
+            "./src/fixtures/deno",
+            &[],
+            None,
+            &mut Counters::default(),
+        );
+        let mut names: Vec<&str> = files
+            .iter()
+            .filter_map(|f| f.file_name()?.to_str())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["deno.json", "deno.jsonc", "package.json"]);
+
+        let duplicates =
+            crate::parser::find_duplicate_dependencies(files, &[], &[], &mut Counters::default())
+                .duplicates;
+
+        let mut found: Vec<&String> = duplicates.keys().collect();
+        found.sort();
+        assert_eq!(found, vec!["@std/assert", "lodash"]);
+        assert_eq!(duplicates["lodash"].len(), 3);
+    }
+}
@@ -0,0 +1,117 @@
+use std::{fmt, time::Duration};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Counters {
+    pub discovered: usize,
+    pub parsed: usize,
+    pub skipped: usize,
+    pub invalid: usize,
+    pub unreadable: usize,
+    pub not_object: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    pub counters: Counters,
+    pub walk: Duration,
+    pub parse: Duration,
+    pub format: Duration,
+}
+
+#[derive(Debug)]
+pub struct Summary {
+    pub duplicates: usize,
+    pub files: usize,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "nodedup: {} duplicated deps in {} files (scanned in {}ms)",
+            self.duplicates,
+            self.files,
+            self.elapsed.as_millis()
+        )
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Walk: {:.2?}", self.walk)?;
+        writeln!(f, "Parse: {:.2?}", self.parse)?;
+        writeln!(f, "Format: {:.2?}", self.format)?;
+        write!(
+            f,
+            "Files discovered: {}, parsed: {}, skipped: {}",
+            self.counters.discovered, self.counters.parsed, self.counters.skipped
+        )?;
+        if self.counters.not_object > 0 {
+            write!(f, ", not an object: {}", self.counters.not_object)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_display_diagnostics() {
+        let diagnostics = Diagnostics {
+            counters: Counters {
+                discovered: 3,
+                parsed: 2,
+                skipped: 1,
+                invalid: 1,
+                unreadable: 0,
+                not_object: 0,
+            },
+            walk: Duration::from_millis(1),
+            parse: Duration::from_millis(2),
+            format: Duration::from_micros(500),
+        };
+
+        assert_eq!(
+            diagnostics.to_string(),
+            "Walk: 1.00ms\nParse: 2.00ms\nFormat: 500.00µs\nFiles discovered: 3, parsed: 2, skipped: 1"
+        );
+    }
+
+    #[test]
+    fn it_should_display_summary() {
+        let summary = Summary {
+            duplicates: 7,
+            files: 120,
+            elapsed: Duration::from_micros(340_500),
+        };
+
+        assert_eq!(
+            summary.to_string(),
+            "nodedup: 7 duplicated deps in 120 files (scanned in 340ms)"
+        );
+    }
+
+    #[test]
+    fn it_should_count_fixture_files() {
+        let mut counters = Counters::default();
+        let files =
+            crate::lookup::get_package_json_files("./src/fixtures/stats", &[], None, &mut counters);
+        crate::parser::find_duplicate_dependencies(files, &[], &[], &mut counters);
+
+        assert_eq!(
+            counters,
+            Counters {
+                discovered: 3,
+                parsed: 3,
+                skipped: 0,
+                invalid: 0,
+                unreadable: 0,
+                not_object: 0,
+            }
+        );
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    panic,
+};
+
+use clap::ValueEnum;
+
+use crate::parser::{is_dev_only, PackageValue};
+
+pub const SUCCESS: i32 = 0;
+pub const FAILURE: i32 = 1;
+pub const USAGE_ERROR: i32 = 2;
+pub const WALK_ERROR: i32 = 3;
+pub const IO_ERROR: i32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum FailOn {
+    #[default]
+    Duplicates,
+    Invalid,
+    Missing,
+    None,
+}
+
+#[derive(Debug, Default)]
+pub struct Failures {
+    pub duplicates: usize,
+    pub invalid: usize,
+    pub missing: usize,
+}
+
+impl Failures {
+    pub fn count(&self, fail_on: FailOn) -> usize {
+        match fail_on {
+            FailOn::Duplicates => self.duplicates,
+            FailOn::Invalid => self.invalid,
+            FailOn::Missing => self.missing,
+            FailOn::None => 0,
+        }
+    }
+}
+
+pub fn get_exit_code(duplicates: usize, silent: bool, silent_threshold: Option<usize>) -> i32 {
+    if silent {
+        return SUCCESS;
+    }
+
+    match silent_threshold {
+        Some(threshold) if duplicates <= threshold => SUCCESS,
+        _ if duplicates == 0 => SUCCESS,
+        _ => FAILURE,
+    }
+}
+
+// Keep in sync with the codes returned by main
+pub fn explain_exit_codes() -> String {
+    [
+        (
+            SUCCESS.to_string(),
+            "Nothing to fail on was found, or failures were silenced by --silent, \
+             --silent-threshold or --fail-on none",
+        ),
+        (
+            FAILURE.to_string(),
+            "Something counted by --fail-on was found, duplicated dependencies by default. \
+             With --fix --dry-run, there are changes that would be made",
+        ),
+        (
+            USAGE_ERROR.to_string(),
+            "Invalid command line arguments, such as an unknown --output style",
+        ),
+        (
+            WALK_ERROR.to_string(),
+            "With --strict, a folder could not be read while scanning",
+        ),
+        (
+            IO_ERROR.to_string(),
+            "A file the run depends on could not be read, parsed or written, such as a missing \
+             scan path or a broken baseline, policy or lockfile, or the file watcher failed to start",
+        ),
+    ]
+    .iter()
+    .map(|(code, meaning)| format!("{:>3}  {}\n", code, meaning))
+    .collect()
+}
+
+// Files that cannot be read or written abort the run with their message instead of a backtrace
+pub fn exit_on_panic() {
+    panic::set_hook(Box::new(|info| {
+        eprintln!("nodedup: {}", panic_message(info.payload()));
+        std::process::exit(IO_ERROR);
+    }));
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unexpected error")
+}
+
+pub fn count_failing(
+    duplicates: &HashMap<String, Vec<PackageValue>>,
+    dev_as_warning: bool,
+) -> usize {
+    duplicates
+        .values()
+        .filter(|values| !(dev_as_warning && is_dev_only(values)))
+        .count()
+}
+
+// Groups already recorded in the baseline are still listed, only new ones fail the run
+pub fn count_new_failing(
+    duplicates: &HashMap<String, Vec<PackageValue>>,
+    added: &HashSet<String>,
+    dev_as_warning: bool,
+) -> usize {
+    duplicates
+        .iter()
+        .filter(|(name, values)| added.contains(*name) && !(dev_as_warning && is_dev_only(values)))
+        .count()
+}
+
+// The share of unique dependencies that are duplicated, e.g. 5 of 200 is 2.5%
+pub fn exceeds_percent(duplicated: usize, total: usize, percent: f64) -> bool {
+    if total == 0 {
+        return false;
+    }
+
+    duplicated as f64 / total as f64 * 100.0 > percent
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Section;
+
+    fn duplicates() -> HashMap<String, Vec<PackageValue>> {
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        hash_map.insert(
+            "jest".to_string(),
+            vec![
+                PackageValue::new("jest", "2.0.0", "a").with_section(Section::DevDependencies),
+                PackageValue::new("jest", "1.0.0", "b").with_section(Section::DevDependencies),
+            ],
+        );
+        hash_map.insert(
+            "react".to_string(),
+            vec![
+                PackageValue::new("react", "2.0.0", "a"),
+                PackageValue::new("react", "1.0.0", "b").with_section(Section::DevDependencies),
+            ],
+        );
+        hash_map
+    }
+
+    #[test]
+    fn it_should_fail_only_above_percent() {
+        assert!(!exceeds_percent(1, 4, 25.0));
+        assert!(exceeds_percent(1, 4, 24.9));
+        assert!(exceeds_percent(5, 200, 2.0));
+        assert!(!exceeds_percent(5, 200, 2.5));
+        assert!(!exceeds_percent(0, 0, 0.0));
+    }
+
+    #[test]
+    fn it_should_count_only_groups_missing_from_baseline() {
+        let added = HashSet::from(["react".to_string()]);
+
+        assert_eq!(count_new_failing(&duplicates(), &added, false), 1);
+        assert_eq!(count_new_failing(&duplicates(), &HashSet::new(), false), 0);
+        assert_eq!(count_failing(&duplicates(), false), 2);
+    }
+
+    #[test]
+    fn it_should_fail_on_chosen_failure() {
+        let failures = Failures {
+            duplicates: 3,
+            invalid: 1,
+            missing: 2,
+        };
+        let exit_code = |fail_on| get_exit_code(failures.count(fail_on), false, None);
+
+        assert_eq!(exit_code(FailOn::Duplicates), FAILURE);
+        assert_eq!(exit_code(FailOn::Invalid), FAILURE);
+        assert_eq!(exit_code(FailOn::Missing), FAILURE);
+        assert_eq!(exit_code(FailOn::None), SUCCESS);
+    }
+
+    #[test]
+    fn it_should_fail_on_duplicates_by_default() {
+        let failures = Failures {
+            duplicates: 3,
+            ..Failures::default()
+        };
+
+        assert_eq!(failures.count(FailOn::default()), 3);
+    }
+
+    #[test]
+    fn it_should_count_every_group_by_default() {
+        assert_eq!(count_failing(&duplicates(), false), 2);
+    }
+
+    #[test]
+    fn it_should_not_count_dev_only_groups_as_warnings() {
+        let mut duplicates = duplicates();
+        assert_eq!(count_failing(&duplicates, true), 1);
+
+        duplicates.remove("react");
+        assert_eq!(count_failing(&duplicates, true), 0);
+        assert_eq!(
+            get_exit_code(count_failing(&duplicates, true), false, None),
+            0
+        );
+    }
+
+    #[test]
+    fn it_should_explain_every_exit_code() {
+        let explanation = explain_exit_codes();
+
+        for code in [SUCCESS, FAILURE, USAGE_ERROR, WALK_ERROR, IO_ERROR] {
+            assert!(explanation
+                .lines()
+                .any(|line| line.trim_start().starts_with(&format!("{} ", code))));
+        }
+        assert_eq!(explanation.lines().count(), 5);
+    }
+
+    #[test]
+    fn it_should_read_panic_messages() {
+        let formatted: Box<dyn Any + Send> =
+            Box::new(format!("Failed to read the policy: {}", "a.json"));
+        let literal: Box<dyn Any + Send> = Box::new("No workspaces declared");
+        let other: Box<dyn Any + Send> = Box::new(1);
+
+        assert_eq!(
+            panic_message(&*formatted),
+            "Failed to read the policy: a.json"
+        );
+        assert_eq!(panic_message(&*literal), "No workspaces declared");
+        assert_eq!(panic_message(&*other), "unexpected error");
+    }
+
+    #[test]
+    fn it_should_return_failure_when_duplicates_found() {
+        assert_eq!(get_exit_code(3, false, None), FAILURE);
+        assert_eq!(get_exit_code(0, false, None), SUCCESS);
+    }
+
+    #[test]
+    fn it_should_not_overlap_error_codes() {
+        assert_eq!(get_exit_code(USAGE_ERROR as usize, false, None), FAILURE);
+        assert_eq!(get_exit_code(WALK_ERROR as usize, false, None), FAILURE);
+        assert_eq!(get_exit_code(256, false, None), FAILURE);
+    }
+
+    #[test]
+    fn it_should_return_zero_when_silent() {
+        assert_eq!(get_exit_code(3, true, None), 0);
+        assert_eq!(get_exit_code(3, true, Some(1)), 0);
+    }
+
+    #[test]
+    fn it_should_return_zero_up_to_threshold() {
+        assert_eq!(get_exit_code(0, false, Some(2)), 0);
+        assert_eq!(get_exit_code(2, false, Some(2)), 0);
+    }
+
+    #[test]
+    fn it_should_return_failure_above_threshold() {
+        assert_eq!(get_exit_code(3, false, Some(2)), FAILURE);
+    }
+
+    #[test]
+    fn it_should_fail_on_any_duplicate_with_zero_threshold() {
+        assert_eq!(get_exit_code(0, false, Some(0)), 0);
+        assert_eq!(get_exit_code(1, false, Some(0)), 1);
+    }
+}
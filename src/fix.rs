@@ -0,0 +1,384 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{BufRead, Write},
+    ops::Range,
+    path::PathBuf,
+};
+
+use regex::Regex;
+
+use crate::parser::{compare_versions, PackageValue, Section};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Edit {
+    pub path: PathBuf,
+    pub section: Section,
+    pub name: String,
+    pub from: String,
+    pub to: String,
+}
+
+// Distinct ranges of a group, highest version first
+fn get_choices(values: &[PackageValue]) -> Vec<&PackageValue> {
+    let mut choices: Vec<&PackageValue> = Vec::new();
+    for value in values {
+        if !choices.iter().any(|c| c.version == value.version) {
+            choices.push(value);
+        }
+    }
+    choices.sort_by(|a, b| compare_versions(&b.version, &a.version));
+
+    choices
+}
+
+pub fn choose_highest(duplicates: &HashMap<String, Vec<PackageValue>>) -> BTreeMap<String, String> {
+    duplicates
+        .iter()
+        .filter_map(|(name, values)| {
+            Some((name.clone(), get_choices(values).first()?.range.clone()))
+        })
+        .collect()
+}
+
+pub fn prompt_choices(
+    duplicates: &HashMap<String, Vec<PackageValue>>,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> BTreeMap<String, String> {
+    let mut names: Vec<&String> = duplicates.keys().collect();
+    names.sort();
+
+    let mut chosen: BTreeMap<String, String> = BTreeMap::new();
+    for name in names {
+        let choices = get_choices(&duplicates[name]);
+        writeln!(output, "{}", name).unwrap();
+        for (i, choice) in choices.iter().enumerate() {
+            writeln!(output, "  {}) {}", i + 1, choice.range).unwrap();
+        }
+
+        let choice = loop {
+            write!(output, "Version to keep [1]: ").unwrap();
+            output.flush().unwrap();
+            let mut line = String::new();
+            // End of input takes the default for this and every remaining group
+            if input.read_line(&mut line).unwrap_or(0) == 0 {
+                break choices[0];
+            }
+            match line.trim() {
+                "" => break choices[0],
+                answer => match answer.parse::<usize>() {
+                    Ok(i) if (1..=choices.len()).contains(&i) => break choices[i - 1],
+                    _ => writeln!(output, "Pick a number from 1 to {}", choices.len()).unwrap(),
+                },
+            }
+        };
+        chosen.insert(name.clone(), choice.range.clone());
+    }
+
+    chosen
+}
+
+pub fn compute_edits(
+    duplicates: &HashMap<String, Vec<PackageValue>>,
+    chosen: &BTreeMap<String, String>,
+) -> Vec<Edit> {
+    chosen
+        .iter()
+        .flat_map(|(name, to)| {
+            // Group keys may be lowercased or carry a section, the edit needs the name as written
+            duplicates
+                .get(name)
+                .into_iter()
+                .flatten()
+                .filter(move |v| &v.range != to)
+                .map(move |v| Edit {
+                    path: v.path.clone(),
+                    section: v.section,
+                    name: v.name.clone(),
+                    from: v.range.clone(),
+                    to: to.clone(),
+                })
+        })
+        .collect()
+}
+
+// Every occurrence of the group other than the chosen range was rewritten
+pub fn is_fixed(values: &[PackageValue], to: &str, applied: &[Edit]) -> bool {
+    values.iter().filter(|v| v.range != to).all(|v| {
+        applied
+            .iter()
+            .any(|edit| edit.path == v.path && edit.name == v.name && edit.from == v.range)
+    })
+}
+
+// The files are edited as text so their formatting and key order stay as they were.
+// Returns the edits that were written, the others are skipped with a warning
+pub fn apply_edits(edits: Vec<Edit>) -> Vec<Edit> {
+    edits
+        .into_iter()
+        .filter(|edit| {
+            let content = fs::read_to_string(&edit.path).unwrap_or_else(|_| {
+                panic!("Failed to read {}", edit.path.display());
+            });
+            let Some(replaced) = replace_in_section(&content, edit) else {
+                log::warn!(
+                    "Skipping {}: {} {} not found in {}",
+                    edit.path.display(),
+                    edit.name,
+                    edit.from,
+                    edit.section
+                );
+                return false;
+            };
+            fs::write(&edit.path, replaced).unwrap_or_else(|_| {
+                panic!("Failed to write {}", edit.path.display());
+            });
+            true
+        })
+        .collect()
+}
+
+// Only the key in the scanned section is replaced, peerDependencies or overrides may repeat it
+fn replace_in_section(content: &str, edit: &Edit) -> Option<String> {
+    let section = find_section(content, &edit.section.to_string())?;
+    let pattern = format!(
+        r#"("{}"\s*:\s*)"{}""#,
+        regex::escape(&edit.name),
+        regex::escape(&edit.from)
+    );
+    let caps = Regex::new(&pattern)
+        .unwrap()
+        .captures(&content[section.clone()])?;
+    let found = caps.get(0)?;
+
+    Some(format!(
+        r#"{}{}"{}"{}"#,
+        &content[..section.start + found.start()],
+        &caps[1],
+        edit.to,
+        &content[section.start + found.end()..]
+    ))
+}
+
+// Byte range of the object under a top-level key, nested objects with the same key are skipped
+fn find_section(content: &str, key: &str) -> Option<Range<usize>> {
+    let mut depth = 0;
+    let mut start = None;
+    let mut chars = content.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                let mut escaped = false;
+                let (end, _) = chars.by_ref().find(|(_, c)| {
+                    let closes = *c == '"' && !escaped;
+                    escaped = !escaped && *c == '\\';
+                    closes
+                })?;
+                let value = content[end + 1..]
+                    .trim_start()
+                    .strip_prefix(':')
+                    .map(str::trim_start);
+                if let Some(value) = value.filter(|value| value.starts_with('{')) {
+                    if depth == 1 && start.is_none() && &content[i + 1..end] == key {
+                        start = Some(content.len() - value.len());
+                    }
+                }
+            }
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth -= 1;
+                if let Some(start) = start.filter(|_| depth == 1) {
+                    return Some(start..i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duplicates() -> HashMap<String, Vec<PackageValue>> {
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        hash_map.insert(
+            "lodash".to_string(),
+            vec![
+                PackageValue::new("lodash", "4.0.0", "./a").with_range("^4.0.0"),
+                PackageValue::new("lodash", "3.0.0", "./b").with_range("^3.0.0"),
+            ],
+        );
+        hash_map.insert(
+            "react".to_string(),
+            vec![
+                PackageValue::new("react", "18.0.0", "./a").with_range("^18.0.0"),
+                PackageValue::new("react", "16.0.0", "./c").with_range("16.0.0"),
+                PackageValue::new("react", "17.0.0", "./b").with_range("~17.0.0"),
+            ],
+        );
+        hash_map
+    }
+
+    #[test]
+    fn it_should_choose_highest_versions() {
+        let chosen = choose_highest(&duplicates());
+
+        assert_eq!(chosen["lodash"], "^4.0.0");
+        assert_eq!(chosen["react"], "^18.0.0");
+    }
+
+    #[test]
+    fn it_should_prompt_for_each_group() {
+        let mut input = "2\n\n".as_bytes();
+        let mut output: Vec<u8> = Vec::new();
+
+        let chosen = prompt_choices(&duplicates(), &mut input, &mut output);
+
+        assert_eq!(chosen["lodash"], "^3.0.0");
+        assert_eq!(chosen["react"], "^18.0.0");
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "lodash\n  1) ^4.0.0\n  2) ^3.0.0\nVersion to keep [1]: \
+             react\n  1) ^18.0.0\n  2) ~17.0.0\n  3) 16.0.0\nVersion to keep [1]: "
+        );
+    }
+
+    #[test]
+    fn it_should_ask_again_for_invalid_choice() {
+        let mut input = "5\nx\n3\n".as_bytes();
+        let mut output: Vec<u8> = Vec::new();
+        let mut duplicates = duplicates();
+        duplicates.remove("lodash");
+
+        let chosen = prompt_choices(&duplicates, &mut input, &mut output);
+
+        assert_eq!(chosen["react"], "16.0.0");
+        assert_eq!(
+            String::from_utf8(output)
+                .unwrap()
+                .matches("Pick a number from 1 to 3")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn it_should_compute_edits_for_other_versions() {
+        let mut chosen = BTreeMap::new();
+        chosen.insert("lodash".to_string(), "^4.0.0".to_string());
+
+        let edits = compute_edits(&duplicates(), &chosen);
+
+        assert_eq!(
+            edits,
+            vec![Edit {
+                path: PathBuf::from("./b"),
+                section: Section::Dependencies,
+                name: "lodash".to_string(),
+                from: "^3.0.0".to_string(),
+                to: "^4.0.0".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn it_should_preview_edits_without_writing() {
+        let dir = std::env::temp_dir().join("nodedup-fix-dry-run");
+        let a = dir.join("a/package.json");
+        let b = dir.join("b/package.json");
+        let content_a = r#"{ "dependencies": { "lodash": "^4.0.0" } }"#;
+        let content_b = r#"{ "dependencies": { "lodash": "^3.0.0" } }"#;
+        fs::create_dir_all(a.parent().unwrap()).unwrap();
+        fs::create_dir_all(b.parent().unwrap()).unwrap();
+        fs::write(&a, content_a).unwrap();
+        fs::write(&b, content_b).unwrap();
+        let duplicates = crate::parser::find_duplicate_dependencies(
+            vec![a.clone(), b.clone()],
+            &[],
+            &[],
+            &mut crate::diagnostics::Counters::default(),
+        )
+        .duplicates;
+
+        let edits = compute_edits(&duplicates, &choose_highest(&duplicates));
+        let preview =
+            crate::formatter::format_edits(&edits, true, crate::formatter::ColorScheme::None);
+
+        assert_eq!(
+            preview,
+            format!("Would update:\n{}: lodash ^3.0.0 -> ^4.0.0\n", b.display())
+        );
+        assert_eq!(fs::read_to_string(&a).unwrap(), content_a);
+        assert_eq!(fs::read_to_string(&b).unwrap(), content_b);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_should_apply_edits_keeping_formatting() {
+        let path = std::env::temp_dir().join("nodedup-fix-test.json");
+        let content = "{\n  \"dependencies\": {\n    \"lodash\":  \"^3.0.0\",\n    \"lodash.merge\": \"^3.0.0\"\n  }\n}\n";
+        fs::write(&path, content).unwrap();
+
+        apply_edits(vec![Edit {
+            path: path.clone(),
+            section: Section::Dependencies,
+            name: "lodash".to_string(),
+            from: "^3.0.0".to_string(),
+            to: "^4.0.0".to_string(),
+        }]);
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{\n  \"dependencies\": {\n    \"lodash\":  \"^4.0.0\",\n    \"lodash.merge\": \"^3.0.0\"\n  }\n}\n"
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_should_only_edit_scanned_section() {
+        let path = std::env::temp_dir().join("nodedup-fix-section-test.json");
+        let content = r#"{
+  "overrides": { "lodash": "^3.0.0" },
+  "peerDependencies": { "lodash": "^3.0.0" },
+  "devDependencies": { "lodash": "^3.0.0" },
+  "resolutions": { "lodash": "^3.0.0" }
+}"#;
+        fs::write(&path, content).unwrap();
+        let edit = |section| Edit {
+            path: path.clone(),
+            section,
+            name: "lodash".to_string(),
+            from: "^3.0.0".to_string(),
+            to: "^4.0.0".to_string(),
+        };
+
+        let applied = apply_edits(vec![
+            edit(Section::DevDependencies),
+            edit(Section::Dependencies),
+        ]);
+
+        assert_eq!(applied, vec![edit(Section::DevDependencies)]);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            content.replace(
+                r#""devDependencies": { "lodash": "^3.0.0" }"#,
+                r#""devDependencies": { "lodash": "^4.0.0" }"#
+            )
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_should_find_top_level_sections_only() {
+        let content = r#"{ "a": { "dependencies": { "x": "1" } }, "dependencies": { "y": "{" } }"#;
+
+        let section = find_section(content, "dependencies").unwrap();
+
+        assert_eq!(&content[section], r#"{ "y": "{" }"#);
+        assert_eq!(find_section(content, "devDependencies"), None);
+    }
+}
@@ -0,0 +1,3 @@
+const merge = require("lodash/merge");
+
+module.exports = merge;
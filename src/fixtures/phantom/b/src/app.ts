@@ -0,0 +1,9 @@
+import React from "react";
+import { merge } from 'lodash';
+import type { Button } from "@acme/ui/button";
+import { readFile } from "fs";
+import { join } from "node:path";
+import { helper } from "./helper";
+export { format } from "date-fns";
+
+const chunk = await import("left-pad");
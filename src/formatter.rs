@@ -1,110 +1,1160 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
 use std::marker::PhantomData;
+use std::path::{Component, Path, PathBuf};
 
+use clap::ValueEnum;
 use colored::*;
+use serde::Serialize;
 
-use crate::parser::PackageValue;
+use crate::baseline::VersionChange;
+use crate::fix::Edit;
+use crate::lockfile::LockViolation;
+use crate::npmrc::Registries;
+use crate::parser::{
+    count_files, count_versions, get_scope, get_spread, get_versions, BundledMismatch,
+    CaseCollision, DuplicateKey, IgnoreEntry, PackageValue, SameVersionGroup,
+};
+use crate::phantom::PhantomDependency;
+use crate::policy::PolicyViolation;
+#[cfg(feature = "registry-check")]
+use crate::registry::UnpublishedVersion;
+use crate::types::TypesMismatch;
 
 #[derive(Debug, PartialEq)]
 enum FormatStyles {
     Default,
     Full,
     Short,
+    Toml,
+    Json,
+    Table,
+    Line,
+    Ndjson,
+    Yaml,
+    Dot,
+    Junit,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ColorScheme {
+    #[default]
+    Default,
+    HighContrast,
+    None,
+}
+
+impl ColorScheme {
+    // Names, counts and versions worth a look
+    fn highlight(self, text: &str) -> ColoredString {
+        match self {
+            ColorScheme::Default => text.red(),
+            ColorScheme::HighContrast => text.bright_yellow().bold(),
+            ColorScheme::None => text.normal(),
+        }
+    }
+
+    // Section titles such as "Locations:"
+    fn heading(self, text: &str) -> ColoredString {
+        match self {
+            ColorScheme::Default => text.green(),
+            ColorScheme::HighContrast => text.bright_cyan().bold(),
+            ColorScheme::None => text.normal(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct DependencyGroup {
+    spread: String,
+    versions: Vec<String>,
+    paths: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct DependencyEntry<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    group: DependencyGroup,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonReport<'a> {
+    count: usize,
+    duplicates: Vec<DependencyEntry<'a>>,
 }
 
 pub struct Empty {}
 pub struct Ready {}
 
+const UNSCOPED: &str = "(unscoped)";
+const STYLES: [&str; 12] = [
+    "default", "short", "full", "toml", "json", "table", "line", "ndjson", "yaml", "dot", "junit",
+    "none",
+];
+const TABLE_HEADER: [&str; 3] = ["Package", "Versions", "Files"];
+const MAX_COLUMN_WIDTH: usize = 40;
+
 pub struct DependenciesFormatter<State = Empty> {
     state: PhantomData<State>,
     style: FormatStyles,
+    group_scope: bool,
+    relative_root: Option<String>,
+    stats: Option<(usize, usize)>,
+    json_pretty: bool,
+    count_only: bool,
+    dedupe_paths: bool,
+    compact_locations: bool,
+    by_major: bool,
+    registries: Option<Registries>,
+    max_lines: Option<usize>,
+    new_groups: Option<HashSet<String>>,
+    top: Option<usize>,
+    // Kept apart from the map, which --top trims and --all fills with the inventory
+    duplicate_count: usize,
     dependencies: HashMap<String, Vec<PackageValue>>,
 }
 
 impl DependenciesFormatter<Empty> {
     pub fn new(dependencies: HashMap<String, Vec<PackageValue>>) -> DependenciesFormatter<Ready> {
         DependenciesFormatter {
+            duplicate_count: dependencies.len(),
             dependencies,
             style: FormatStyles::Default,
+            group_scope: false,
+            relative_root: None,
+            stats: None,
+            json_pretty: false,
+            count_only: false,
+            dedupe_paths: false,
+            compact_locations: false,
+            by_major: false,
+            registries: None,
+            max_lines: None,
+            new_groups: None,
+            top: None,
             state: PhantomData::<Ready>,
         }
     }
 }
 
 impl DependenciesFormatter<Ready> {
-    pub fn try_set_style(&mut self, style: &str) {
-        if ["short", "default", "full"].iter().any(|v| v == &style) {
+    pub fn try_set_style(&mut self, style: &str) -> Result<(), String> {
+        if STYLES.iter().any(|v| v == &style) {
             self.set_style(match style {
                 "short" => FormatStyles::Short,
                 "full" => FormatStyles::Full,
+                "toml" => FormatStyles::Toml,
+                "json" => FormatStyles::Json,
+                "table" => FormatStyles::Table,
+                "line" => FormatStyles::Line,
+                "ndjson" => FormatStyles::Ndjson,
+                "yaml" => FormatStyles::Yaml,
+                "dot" => FormatStyles::Dot,
+                "junit" => FormatStyles::Junit,
+                "none" => FormatStyles::None,
                 _ => FormatStyles::Default,
             });
-            return;
+            return Ok(());
         }
 
-        panic!("Unknown style format: {}", style)
+        match suggest_style(style) {
+            Some(suggestion) => Err(format!(
+                "Unknown style format: {}. Did you mean '{}'?",
+                style, suggestion
+            )),
+            None => Err(format!(
+                "Unknown style format: {}. Possible values: {}",
+                style,
+                STYLES.join(", ")
+            )),
+        }
     }
 
     fn set_style(&mut self, style: FormatStyles) {
         self.style = style
     }
 
-    pub fn format(&self, color: bool) -> String {
+    pub fn set_group_scope(&mut self, group_scope: bool) {
+        self.group_scope = group_scope
+    }
+
+    pub fn set_relative_root(&mut self, relative_root: Option<String>) {
+        self.relative_root = relative_root
+    }
+
+    pub fn set_stats(&mut self, files_scanned: usize, total_dependencies: usize) {
+        self.stats = Some((files_scanned, total_dependencies))
+    }
+
+    pub fn set_json_pretty(&mut self, json_pretty: bool) {
+        self.json_pretty = json_pretty
+    }
+
+    // Keeps the groups with the most distinct versions, ties are broken by name
+    pub fn set_top(&mut self, top: Option<usize>) {
+        self.top = top;
+        let Some(top) = top else {
+            return;
+        };
+
+        let kept: Vec<String> = by_version_count(&self.dependencies)
+            .into_iter()
+            .take(top)
+            .map(|(name, _)| name.clone())
+            .collect();
+        self.dependencies.retain(|name, _| kept.contains(name));
+    }
+
+    pub fn set_duplicate_count(&mut self, duplicate_count: usize) {
+        self.duplicate_count = duplicate_count
+    }
+
+    pub fn set_count_only(&mut self, count_only: bool) {
+        self.count_only = count_only
+    }
+
+    pub fn set_dedupe_paths(&mut self, dedupe_paths: bool) {
+        self.dedupe_paths = dedupe_paths
+    }
+
+    pub fn set_compact_locations(&mut self, compact_locations: bool) {
+        self.compact_locations = compact_locations
+    }
+
+    pub fn set_by_major(&mut self, by_major: bool) {
+        self.by_major = by_major
+    }
+
+    pub fn set_new_groups(&mut self, new_groups: Option<HashSet<String>>) {
+        self.new_groups = new_groups
+    }
+
+    pub fn set_registries(&mut self, registries: Option<Registries>) {
+        self.registries = registries
+    }
+
+    pub fn set_max_lines(&mut self, max_lines: Option<usize>) {
+        self.max_lines = max_lines
+    }
+
+    pub fn is_machine_readable(&self) -> bool {
+        matches!(
+            self.style,
+            FormatStyles::Toml
+                | FormatStyles::Json
+                | FormatStyles::Ndjson
+                | FormatStyles::Yaml
+                | FormatStyles::Dot
+                | FormatStyles::Junit
+                | FormatStyles::Line
+        )
+    }
+
+    pub fn format(&self, colors: ColorScheme) -> String {
+        // Only the exit code is wanted
+        if self.style == FormatStyles::None {
+            return String::new();
+        }
+        if self.count_only {
+            return self.duplicate_count.to_string();
+        }
+        if let Some((files_scanned, total_dependencies)) = self.stats {
+            return apply_color(
+                self.format_stats(files_scanned, total_dependencies, colors),
+                colors,
+            );
+        }
+
+        let formatted = match self.style {
+            // Cutting these would leave output that cannot be parsed
+            FormatStyles::Toml => return self.format_toml(),
+            FormatStyles::Json => return self.format_json(),
+            FormatStyles::Ndjson => return self.format_ndjson(),
+            FormatStyles::Yaml => return self.format_yaml(),
+            FormatStyles::Dot => return self.format_dot(),
+            FormatStyles::Junit => return self.format_junit(),
+            FormatStyles::Table => apply_color(self.format_table(colors), colors),
+            FormatStyles::Line => self.format_lines(),
+            _ => apply_color(self.format_text(colors), colors),
+        };
+
+        match self.max_lines {
+            Some(max_lines) => truncate_lines(formatted, max_lines),
+            None => formatted,
+        }
+    }
+
+    fn format_text(&self, colors: ColorScheme) -> String {
         let mut formatted = String::new();
 
-        for (name, values) in &self.dependencies {
+        if self.group_scope {
+            formatted.push_str(&self.format_scopes(colors));
+        }
+
+        for (name, values) in self.ordered_dependencies() {
+            formatted.push_str(&self.format_dependency(name, values, colors));
+        }
+
+        formatted
+    }
+
+    fn format_toml(&self) -> String {
+        toml::to_string(&self.dependency_groups()).unwrap()
+    }
+
+    fn format_json(&self) -> String {
+        let report = JsonReport {
+            count: self.dependencies.len(),
+            duplicates: self
+                .dependency_groups()
+                .into_iter()
+                .map(|(name, group)| DependencyEntry { name, group })
+                .collect(),
+        };
+
+        if self.json_pretty {
+            serde_json::to_string_pretty(&report).unwrap()
+        } else {
+            serde_json::to_string(&report).unwrap()
+        }
+    }
+
+    // One self-contained object per group, the trailing newline comes with the printed report
+    fn format_ndjson(&self) -> String {
+        self.dependency_groups()
+            .into_iter()
+            .map(|(name, group)| serde_json::to_string(&DependencyEntry { name, group }).unwrap())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn format_yaml(&self) -> String {
+        let entries: Vec<DependencyEntry> = self
+            .dependency_groups()
+            .into_iter()
+            .map(|(name, group)| DependencyEntry { name, group })
+            .collect();
+
+        serde_yaml::to_string(&entries)
+            .unwrap()
+            .trim_end()
+            .to_string()
+    }
+
+    fn format_dot(&self) -> String {
+        let mut dependencies: Vec<(&String, &Vec<PackageValue>)> =
+            self.dependencies.iter().collect();
+        dependencies.sort_by_key(|(name, _)| *name);
+        let mut nodes: Vec<String> = Vec::new();
+        let mut files: BTreeSet<String> = BTreeSet::new();
+        let mut edges: Vec<String> = Vec::new();
+        for (name, values) in dependencies {
+            nodes.push(format!("  {} [shape=box];", dot_id(name)));
+            for value in values {
+                let path = self.display_path(&value.path);
+                edges.push(format!(
+                    "  {} -- {} [label={}];",
+                    dot_id(name),
+                    dot_id(&path),
+                    dot_id(&value.range)
+                ));
+                files.insert(path);
+            }
+        }
+        nodes.extend(
+            files
+                .iter()
+                .map(|path| format!("  {} [shape=note];", dot_id(path))),
+        );
+
+        format!(
+            "graph nodedup {{\n{}\n}}",
+            [nodes, edges].concat().join("\n")
+        )
+    }
+
+    fn format_junit(&self) -> String {
+        let groups = self.dependency_groups();
+        let testcases = groups
+            .iter()
+            .map(|(name, group)| {
+                let versions = self.dependencies[*name]
+                    .iter()
+                    .zip(&group.paths)
+                    .map(|(value, path)| format!("{} {}", value.range, path))
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                format!(
+                    "    <testcase classname=\"nodedup\" name=\"{}\">\n      \
+                     <failure type=\"duplicate\" message=\"Unique versions: {}, Spread: {}\">{}</failure>\n    \
+                     </testcase>\n",
+                    escape_xml(name),
+                    count_versions(&self.dependencies[*name]),
+                    group.spread,
+                    escape_xml(&versions)
+                )
+            })
+            .collect::<String>();
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n  \
+             <testsuite name=\"nodedup\" tests=\"{0}\" failures=\"{0}\" errors=\"0\">\n{1}  \
+             </testsuite>\n</testsuites>",
+            groups.len(),
+            testcases
+        )
+    }
+
+    fn format_lines(&self) -> String {
+        let mut dependencies: Vec<(&String, &Vec<PackageValue>)> =
+            self.dependencies.iter().collect();
+        dependencies.sort_by_key(|(name, _)| *name);
+
+        dependencies
+            .into_iter()
+            .flat_map(|(name, values)| {
+                values.iter().map(move |v| {
+                    format!("{}\t{}\t{}", name, v.version, self.display_path(&v.path))
+                })
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn format_table(&self, colors: ColorScheme) -> String {
+        let mut dependencies: Vec<(&String, &Vec<PackageValue>)> =
+            self.dependencies.iter().collect();
+        dependencies.sort_by_key(|(name, _)| *name);
+        let rows: Vec<[String; 3]> = dependencies
+            .into_iter()
+            .map(|(name, values)| {
+                let mut versions: Vec<&str> = Vec::new();
+                for value in values {
+                    if !versions.contains(&value.version.as_str()) {
+                        versions.push(&value.version);
+                    }
+                }
+                [
+                    truncate(name),
+                    truncate(&versions.join(", ")),
+                    count_files(values).to_string(),
+                ]
+            })
+            .collect();
+
+        // Widths are measured before coloring so escape codes do not break the alignment
+        let widths: Vec<usize> = (0..TABLE_HEADER.len())
+            .map(|i| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([TABLE_HEADER[i].len()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        let pad = |cells: [&str; 3]| -> Vec<String> {
+            cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect()
+        };
+
+        let header = pad(TABLE_HEADER).join(" | ");
+        let mut formatted = format!("{}\n", colors.heading(header.trim_end()));
+        for [name, versions, files] in &rows {
+            let mut cells = pad([name, versions, files]);
+            cells[0] = colors.highlight(&cells[0]).to_string();
+            formatted.push_str(&format!("{}\n", cells.join(" | ").trim_end()));
+        }
+
+        formatted
+    }
+
+    fn dependency_groups(&self) -> BTreeMap<&str, DependencyGroup> {
+        self.dependencies
+            .iter()
+            .map(|(name, values)| {
+                let group = DependencyGroup {
+                    spread: get_spread(values).to_string(),
+                    versions: values.iter().map(|v| v.version.clone()).collect(),
+                    paths: values.iter().map(|v| self.display_path(&v.path)).collect(),
+                };
+                (name.as_str(), group)
+            })
+            .collect()
+    }
+
+    fn ordered_dependencies(&self) -> Vec<(&String, &Vec<PackageValue>)> {
+        let mut dependencies: Vec<(&String, &Vec<PackageValue>)> = if self.top.is_some() {
+            by_version_count(&self.dependencies)
+        } else {
+            let mut dependencies: Vec<(&String, &Vec<PackageValue>)> =
+                self.dependencies.iter().collect();
+            dependencies.sort_by_key(|(name, _)| *name);
+            dependencies
+        };
+        if self.group_scope {
+            dependencies
+                .sort_by_key(|(name, _)| (get_scope(name).is_none(), get_scope(name), *name));
+        }
+
+        dependencies
+    }
+
+    fn format_stats(
+        &self,
+        files_scanned: usize,
+        total_dependencies: usize,
+        colors: ColorScheme,
+    ) -> String {
+        let worst = self
+            .dependencies
+            .iter()
+            .map(|(name, values)| (name, count_versions(values)))
+            .max_by(|(a_name, a), (b_name, b)| a.cmp(b).then(b_name.cmp(a_name)))
+            .map(|(name, versions)| format!("{} ({} versions)", colors.highlight(name), versions))
+            .unwrap_or_else(|| "none".to_string());
+
+        format!(
+            "Files scanned: {}\nUnique dependencies: {}\nDuplicated dependencies: {}\nWorst offender: {}\n",
+            files_scanned,
+            total_dependencies,
+            colors.highlight(&self.duplicate_count.to_string()),
+            worst
+        )
+    }
+
+    fn format_scopes(&self, colors: ColorScheme) -> String {
+        let mut scopes: Vec<(Option<&str>, usize)> = Vec::new();
+        for (name, _) in self.ordered_dependencies() {
+            let scope = get_scope(name);
+            match scopes.last_mut() {
+                Some((last, count)) if *last == scope => *count += 1,
+                _ => scopes.push((scope, 1)),
+            }
+        }
+
+        format!(
+            "{}{}\n",
+            colors.heading("Scopes:\n"),
+            scopes
+                .iter()
+                .map(|(scope, count)| {
+                    format!(
+                        "{}: {}\n",
+                        scope.unwrap_or(UNSCOPED),
+                        colors.highlight(&count.to_string())
+                    )
+                })
+                .collect::<String>()
+        )
+    }
+
+    // Paths are only turned into text here, non-UTF-8 parts are shown as U+FFFD
+    fn display_path(&self, path: &Path) -> String {
+        let path = portable_path(path);
+        let relative = self.relative_root.as_ref().and_then(|root| {
+            let root = portable_path(Path::new(root));
+            let relative = without_cur_dir(Path::new(&path))
+                .strip_prefix(without_cur_dir(Path::new(&root)))
+                .ok()?
+                .to_path_buf();
+            Some(relative.display().to_string())
+        });
+
+        relative.unwrap_or(path)
+    }
+
+    fn display_location(&self, value: &PackageValue) -> String {
+        match (&self.style, &value.package) {
+            (FormatStyles::Full, Some(package)) => package.clone(),
+            _ => self.display_path(&value.path),
+        }
+    }
+
+    fn format_dependency(
+        &self,
+        name: &str,
+        values: &[PackageValue],
+        colors: ColorScheme,
+    ) -> String {
+        let registry = match (&self.registries, get_scope(name)) {
+            (Some(registries), Some(_)) => format!(", Registry: {}", registries.resolve(name)),
+            _ => String::new(),
+        };
+        let files = match self.style {
+            FormatStyles::Short => format!(", Files: {}", count_files(values)),
+            _ => String::new(),
+        };
+        let title = match &self.new_groups {
+            Some(new_groups) if new_groups.contains(name) => {
+                format!("{} (new)", colors.highlight(name))
+            }
+            Some(_) => name.to_string(),
+            None => colors.highlight(name).to_string(),
+        };
+        let mut formatted = format!(
+            "{}, Unique versions: {}{}, Spread: {}{}\n",
+            title,
+            colors.highlight(&count_versions(values).to_string()),
+            files,
+            get_spread(values),
+            registry
+        );
+        if self.by_major {
             formatted.push_str(&format!(
-                "{}, Unique versions: {}\n",
-                name.red(),
-                values.len().to_string().red()
+                "{}{}\n",
+                colors.heading("Major versions:\n"),
+                format_major_buckets(values)
             ));
-            if self.style == FormatStyles::Short {
-                continue;
+        }
+        if self.style == FormatStyles::Short {
+            return formatted;
+        }
+        // HashMap and walk order differ between runs, sorting keeps CI logs comparable
+        let mut locations = self.get_locations(values);
+        locations.sort();
+        let separator = if self.compact_locations { ", " } else { "\n" };
+        formatted.push_str(&format!(
+            "{}{}\n\n",
+            colors.heading("Locations:\n"),
+            locations
+                .iter()
+                .map(|(location, _)| location.as_str())
+                .collect::<Vec<&str>>()
+                .join(separator)
+        ));
+        if self.style == FormatStyles::Default {
+            return formatted;
+        }
+        formatted.push_str(&format!(
+            "{}{}\n\n",
+            colors.heading("Versions:\n"),
+            locations
+                .iter()
+                .map(|(_, version)| *version)
+                .collect::<Vec<&str>>()
+                .join("\n")
+        ));
+
+        formatted
+    }
+
+    fn get_locations<'a>(&self, values: &'a [PackageValue]) -> Vec<(String, &'a str)> {
+        if !self.dedupe_paths {
+            return values
+                .iter()
+                .map(|v| (self.display_location(v), v.version.as_str()))
+                .collect();
+        }
+
+        // Package folders of one version that sit next to each other are summarized by their parent
+        let mut groups: Vec<(Option<&Path>, &str, Vec<&Path>)> = Vec::new();
+        for value in values {
+            let parent = value.path.parent().and_then(Path::parent);
+            match groups.iter_mut().find(|(p, version, _)| {
+                parent.is_some() && *p == parent && *version == value.version
+            }) {
+                Some((_, _, paths)) => paths.push(&value.path),
+                None => groups.push((parent, &value.version, vec![&value.path])),
             }
-            formatted.push_str(&format!(
-                "{}{}\n\n",
-                "Locations:\n".green(),
+        }
+
+        groups
+            .into_iter()
+            .map(
+                |(parent, version, paths)| match (parent, paths.as_slice()) {
+                    (Some(parent), [_, _, ..]) => (
+                        format!("{} ({})", self.display_path(&parent.join("*")), paths.len()),
+                        version,
+                    ),
+                    _ => (self.display_path(paths[0]), version),
+                },
+            )
+            .collect()
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn dot_id(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// Paths recorded on Windows may use `\\`, reports always use `/` so they read the same on every
+// platform
+fn portable_path(path: &Path) -> String {
+    path.display().to_string().replace('\\', "/")
+}
+
+// `./repo/a` and `repo/a` point at the same file, so `.` components do not prevent a match
+fn without_cur_dir(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| *c != Component::CurDir)
+        .collect()
+}
+
+fn format_major_buckets(values: &[PackageValue]) -> String {
+    let mut buckets: BTreeMap<Reverse<u64>, HashSet<&PathBuf>> = BTreeMap::new();
+    for value in values {
+        buckets
+            .entry(Reverse(get_versions(&value.version).0))
+            .or_default()
+            .insert(&value.path);
+    }
+
+    buckets
+        .iter()
+        .map(|(Reverse(major), paths)| {
+            let noun = if paths.len() == 1 { "file" } else { "files" };
+            format!("v{}: {} {}\n", major, paths.len(), noun)
+        })
+        .collect()
+}
+
+pub fn format_bundled_mismatches(mismatches: &[BundledMismatch], colors: ColorScheme) -> String {
+    let mut formatted = String::new();
+
+    for mismatch in mismatches {
+        formatted.push_str(&format!(
+            "{}, Bundled in: {} ({})\n{}{}\n\n",
+            colors.highlight(&mismatch.name),
+            portable_path(&mismatch.path),
+            mismatch.version.as_deref().unwrap_or("no version"),
+            colors.heading("Versions elsewhere:\n"),
+            mismatch
+                .elsewhere
+                .iter()
+                .map(|v| format!(
+                    "{} {}",
+                    colors.highlight(&v.version),
+                    portable_path(&v.path)
+                ))
+                .collect::<Vec<String>>()
+                .join("\n")
+        ));
+    }
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_types_mismatches(mismatches: &[TypesMismatch], colors: ColorScheme) -> String {
+    let formatted = mismatches
+        .iter()
+        .map(|mismatch| {
+            format!(
+                "{}, Types out of line in {}: {} with @types {}\n",
+                colors.highlight(&mismatch.name),
+                portable_path(&mismatch.path),
+                mismatch.version,
+                colors.highlight(&mismatch.types_version)
+            )
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_non_registry_dependencies(values: &[PackageValue], colors: ColorScheme) -> String {
+    let formatted = values
+        .iter()
+        .map(|value| {
+            format!(
+                "{}, Not from the registry in {}: {}\n",
+                colors.highlight(&value.name),
+                portable_path(&value.path),
+                value.version
+            )
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_same_version_groups(groups: &[SameVersionGroup], colors: ColorScheme) -> String {
+    let formatted = groups
+        .iter()
+        .map(|group| {
+            format!(
+                "{}, Same version {} in {} files\n",
+                colors.highlight(&group.name),
+                group.range,
+                group.files
+            )
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_version_changes(
+    changes: &[VersionChange],
+    from: &str,
+    to: &str,
+    colors: ColorScheme,
+) -> String {
+    let formatted = changes
+        .iter()
+        .map(|change| {
+            let removed = change.removed.iter().map(|v| format!("- {}\n", v));
+            let added = change.added.iter().map(|v| format!("+ {}\n", v));
+            format!(
+                "{}, Changed from {} to {}:\n{}\n",
+                colors.highlight(&change.name),
+                from,
+                to,
+                removed.chain(added).collect::<String>()
+            )
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+#[cfg(feature = "registry-check")]
+pub fn format_unpublished_versions(
+    unpublished: &[UnpublishedVersion],
+    colors: ColorScheme,
+) -> String {
+    let formatted = unpublished
+        .iter()
+        .map(|u| {
+            format!(
+                "{}, No published version matches: {}\n",
+                colors.highlight(&u.name),
+                u.range
+            )
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_policy_violations(violations: &[PolicyViolation], colors: ColorScheme) -> String {
+    let formatted = violations
+        .iter()
+        .map(|violation| {
+            format!(
+                "{}, Approved {} but declared {} in {}\n",
+                colors.highlight(&violation.name),
+                violation.approved,
+                violation.range,
+                portable_path(&violation.path)
+            )
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_ignored(entries: &[IgnoreEntry], colors: ColorScheme) -> String {
+    let formatted = entries
+        .iter()
+        .map(|entry| match &entry.reason {
+            Some(reason) => format!(
+                "{}, Ignored: {}\n",
+                colors.highlight(&entry.pattern),
+                reason
+            ),
+            None => format!("{}, Ignored\n", colors.highlight(&entry.pattern)),
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_lock_violations(violations: &[LockViolation], colors: ColorScheme) -> String {
+    let formatted = violations
+        .iter()
+        .map(|violation| {
+            format!(
+                "{}, Declared {} in {} but locked at {}\n",
+                colors.highlight(&violation.name),
+                violation.range,
+                portable_path(&violation.path),
+                violation.locked
+            )
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_phantom_dependencies(phantoms: &[PhantomDependency], colors: ColorScheme) -> String {
+    let formatted = phantoms
+        .iter()
+        .map(|phantom| {
+            format!(
+                "{}, Imported but not declared in {}: {}\n",
+                colors.highlight(&phantom.name),
+                portable_path(&phantom.path),
+                portable_path(&phantom.source)
+            )
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_duplicate_keys(keys: &[DuplicateKey], colors: ColorScheme) -> String {
+    let formatted = keys
+        .iter()
+        .map(|key| {
+            format!(
+                "{}, Duplicate key in {}: {}\n",
+                colors.highlight(&key.name),
+                key.section,
+                portable_path(&key.path)
+            )
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_resolution_drift(
+    drift: &HashMap<String, Vec<PackageValue>>,
+    colors: ColorScheme,
+) -> String {
+    let mut names: Vec<&String> = drift.keys().collect();
+    names.sort();
+
+    let mut formatted = String::new();
+    for name in names {
+        let values = &drift[name];
+        formatted.push_str(&format!(
+            "{}, Resolved versions: {}\n{}\n\n",
+            colors.highlight(name),
+            colors.highlight(&count_versions(values).to_string()),
+            values
+                .iter()
+                .map(|v| format!(
+                    "{} {}",
+                    colors.highlight(&v.version),
+                    portable_path(&v.path)
+                ))
+                .collect::<Vec<String>>()
+                .join("\n")
+        ));
+    }
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_case_collisions(collisions: &[CaseCollision], colors: ColorScheme) -> String {
+    let formatted = collisions
+        .iter()
+        .map(|collision| {
+            format!(
+                "{}, Declared with different casing in {}\n",
+                colors.highlight(&collision.names.join(", ")),
+                portable_path(&collision.path)
+            )
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_case_conflicts(
+    conflicts: &BTreeMap<String, Vec<String>>,
+    colors: ColorScheme,
+) -> String {
+    let formatted = conflicts
+        .iter()
+        .map(|(name, spellings)| {
+            format!(
+                "{}, Declared with different casing: {}\n",
+                colors.highlight(name),
+                spellings.join(", ")
+            )
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_recommendations(
+    recommendations: &BTreeMap<String, String>,
+    colors: ColorScheme,
+) -> String {
+    let formatted = format!(
+        "{}{}",
+        colors.heading("Recommended:\n"),
+        recommendations
+            .iter()
+            .map(|(name, version)| format!("{} -> {}\n", name, version))
+            .collect::<String>()
+    );
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_edits(edits: &[Edit], dry_run: bool, colors: ColorScheme) -> String {
+    let header = if dry_run {
+        "Would update:\n"
+    } else {
+        "Updated:\n"
+    };
+    let formatted = format!(
+        "{}{}",
+        colors.heading(header),
+        edits
+            .iter()
+            .map(|edit| {
+                format!(
+                    "{}: {} {} -> {}\n",
+                    portable_path(&edit.path),
+                    colors.highlight(&edit.name),
+                    edit.from,
+                    edit.to
+                )
+            })
+            .collect::<String>()
+    );
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_resolved_duplicates(names: &[String], colors: ColorScheme) -> String {
+    let formatted = format!(
+        "{}{}\n",
+        colors.heading("Resolved since baseline:\n"),
+        names.join("\n")
+    );
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_workspace_protocol_mixes(
+    mixes: &BTreeMap<String, Vec<PackageValue>>,
+    colors: ColorScheme,
+) -> String {
+    let formatted = mixes
+        .iter()
+        .map(|(name, values)| {
+            format!(
+                "{}, Referenced both as a workspace and from the registry:\n{}\n\n",
+                colors.highlight(name),
                 values
                     .iter()
-                    .map(|v| v.path.clone())
+                    .map(|v| format!("{} {}", colors.highlight(&v.range), portable_path(&v.path)))
                     .collect::<Vec<String>>()
                     .join("\n")
-            ));
-            if self.style == FormatStyles::Default {
-                continue;
-            }
-            formatted.push_str(&format!(
-                "{}{}\n\n",
-                "Versions:\n".green(),
-                values
+            )
+        })
+        .collect::<String>();
+
+    apply_color(formatted, colors)
+}
+
+pub fn format_missing_dependencies(
+    missing: &BTreeMap<String, Vec<PathBuf>>,
+    colors: ColorScheme,
+) -> String {
+    let formatted = missing
+        .iter()
+        .filter(|(_, paths)| !paths.is_empty())
+        .map(|(name, paths)| {
+            format!(
+                "{}, Missing in {} files:\n{}\n\n",
+                colors.highlight(name),
+                colors.highlight(&paths.len().to_string()),
+                paths
                     .iter()
-                    .map(|v| v.version.clone())
+                    .map(|path| portable_path(path))
                     .collect::<Vec<String>>()
                     .join("\n")
-            ));
-        }
+            )
+        })
+        .collect::<String>();
 
-        if color {
-            formatted
-        } else {
-            regex::Regex::new("\x1B\\[[0-9;]*m")
-                .unwrap()
-                .replace_all(&formatted, "")
-                .to_string()
-        }
+    apply_color(formatted, colors)
+}
+
+fn truncate_lines(formatted: String, max_lines: usize) -> String {
+    let lines: Vec<&str> = formatted.lines().collect();
+    if lines.len() <= max_lines {
+        return formatted;
+    }
+
+    format!(
+        "{}\n... and {} more (use --out to capture all)\n",
+        lines[..max_lines].join("\n"),
+        lines.len() - max_lines
+    )
+}
+
+fn by_version_count(
+    dependencies: &HashMap<String, Vec<PackageValue>>,
+) -> Vec<(&String, &Vec<PackageValue>)> {
+    let mut dependencies: Vec<(&String, &Vec<PackageValue>)> = dependencies.iter().collect();
+    dependencies.sort_by_key(|(name, values)| (Reverse(count_versions(values)), *name));
+
+    dependencies
+}
+
+fn truncate(cell: &str) -> String {
+    if cell.chars().count() <= MAX_COLUMN_WIDTH {
+        return cell.to_string();
+    }
+
+    let mut truncated: String = cell.chars().take(MAX_COLUMN_WIDTH - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn suggest_style(style: &str) -> Option<&'static str> {
+    STYLES
+        .iter()
+        .map(|candidate| (strsim::levenshtein(style, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= 3)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+pub fn write_report(path: &str, report: &str) {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).unwrap_or_else(|_| {
+            panic!("Failed to create the folder for the report: {}", path);
+        });
+    }
+    fs::write(path, report).unwrap_or_else(|_| {
+        panic!("Failed to write the report: {}", path);
+    });
+}
+
+fn apply_color(formatted: String, colors: ColorScheme) -> String {
+    if colors != ColorScheme::None {
+        formatted
+    } else {
+        regex::Regex::new("\x1B\\[[0-9;]*m")
+            .unwrap()
+            .replace_all(&formatted, "")
+            .to_string()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::diagnostics::Counters;
 
     mod try_set_style {
         use super::*;
 
         #[test]
-        #[should_panic]
-        fn it_should_panic_on_wrong_string() {
+        fn it_should_fail_on_wrong_string() {
             let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
             hash_map.insert(
                 "test".to_string(),
@@ -115,7 +1165,35 @@ mod test {
             );
 
             let mut formatter = DependenciesFormatter::new(hash_map);
-            formatter.try_set_style("error");
+            assert_eq!(
+                formatter.try_set_style("error"),
+                Err(
+                    "Unknown style format: error. Possible values: default, short, full, toml, json, table, line, ndjson, yaml, dot, junit, none"
+                        .to_string()
+                )
+            );
+        }
+
+        #[test]
+        fn it_should_suggest_closest_style() {
+            let mut formatter = DependenciesFormatter::new(HashMap::new());
+
+            assert_eq!(
+                formatter.try_set_style("defualt"),
+                Err("Unknown style format: defualt. Did you mean 'default'?".to_string())
+            );
+            assert_eq!(
+                formatter.try_set_style("shrt"),
+                Err("Unknown style format: shrt. Did you mean 'short'?".to_string())
+            );
+        }
+
+        #[test]
+        fn it_should_set_known_style() {
+            let mut formatter = DependenciesFormatter::new(HashMap::new());
+
+            assert_eq!(formatter.try_set_style("full"), Ok(()));
+            assert_eq!(formatter.style, FormatStyles::Full);
         }
     }
 
@@ -136,37 +1214,309 @@ mod test {
             let mut formatter = DependenciesFormatter::new(hash_map);
             formatter.set_style(FormatStyles::Full);
 
-            let formatted = formatter.format(false);
+            let formatted = formatter.format(ColorScheme::None);
             assert_eq!(
                 regex::Regex::new("\x1B\\[[0-9;]*m")
                     .unwrap()
                     .replace_all(&formatted, "")
                     .to_string(),
-                "test, Unique versions: 2\nLocations:\n./src/1\n./src/2\n\nVersions:\n1.0.0\n2.0.0\n\n"
+                "test, Unique versions: 2, Spread: 1 major\nLocations:\n./src/1\n./src/2\n\nVersions:\n1.0.0\n2.0.0\n\n"
             );
         }
 
         #[test]
-        fn it_should_short_format_dependencies() {
+        fn it_should_mark_groups_missing_from_baseline() {
             let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
-            hash_map.insert(
-                "test".to_string(),
-                vec![
-                    PackageValue::new("test", "1.0.0", "./src/1"),
-                    PackageValue::new("test", "2.0.0", "./src/2"),
-                ],
-            );
+            for name in ["lodash", "react"] {
+                hash_map.insert(
+                    name.to_string(),
+                    vec![
+                        PackageValue::new(name, "1.0.0", "./src/1"),
+                        PackageValue::new(name, "2.0.0", "./src/2"),
+                    ],
+                );
+            }
 
             let mut formatter = DependenciesFormatter::new(hash_map);
             formatter.set_style(FormatStyles::Short);
+            formatter.set_new_groups(Some(HashSet::from(["react".to_string()])));
 
-            let formatted = formatter.format(false);
+            let formatted = formatter.format(ColorScheme::None);
             assert_eq!(
-                regex::Regex::new("\x1B\\[[0-9;]*m")
-                    .unwrap()
+                formatted,
+                "lodash, Unique versions: 2, Files: 2, Spread: 1 major\n\
+                 react (new), Unique versions: 2, Files: 2, Spread: 1 major\n"
+            );
+        }
+
+        #[test]
+        fn it_should_sort_groups_by_name() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            for name in ["react", "@babel/core", "lodash", "express", "vue"] {
+                hash_map.insert(
+                    name.to_string(),
+                    vec![
+                        PackageValue::new(name, "1.0.0", "./src/1"),
+                        PackageValue::new(name, "2.0.0", "./src/2"),
+                    ],
+                );
+            }
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Short);
+
+            let formatted = formatter.format(ColorScheme::None);
+            let names: Vec<&str> = formatted
+                .lines()
+                .filter_map(|line| line.split(',').next())
+                .collect();
+            assert_eq!(
+                names,
+                vec!["@babel/core", "express", "lodash", "react", "vue"]
+            );
+        }
+
+        #[test]
+        fn it_should_sort_locations() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "3.0.0", "./src/c"),
+                    PackageValue::new("test", "1.0.0", "./src/a"),
+                    PackageValue::new("test", "2.0.0", "./src/b"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Full);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "test, Unique versions: 3, Spread: 2 major\n\
+                 Locations:\n./src/a\n./src/b\n./src/c\n\n\
+                 Versions:\n1.0.0\n2.0.0\n3.0.0\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_collapse_sibling_paths() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "2.0.0", "./packages/a/package.json"),
+                    PackageValue::new("test", "2.0.0", "./packages/b/package.json"),
+                    PackageValue::new("test", "2.0.0", "./packages/c/package.json"),
+                    PackageValue::new("test", "2.0.0", "./apps/web/package.json"),
+                    PackageValue::new("test", "1.0.0", "./packages/d/package.json"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Full);
+            formatter.set_dedupe_paths(true);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                regex::Regex::new("\x1B\\[[0-9;]*m")
+                    .unwrap()
+                    .replace_all(&formatted, "")
+                    .to_string(),
+                "test, Unique versions: 2, Spread: 1 major\n\
+                 Locations:\n./apps/web/package.json\n./packages/* (3)\n./packages/d/package.json\n\n\
+                 Versions:\n2.0.0\n2.0.0\n1.0.0\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_show_package_names_in_full_output() {
+            let paths = vec![
+                PathBuf::from("./src/fixtures/sections/a/package.json"),
+                PathBuf::from("./src/fixtures/sections/b/package.json"),
+            ];
+            let mut duplicates = crate::parser::find_duplicate_dependencies(
+                paths,
+                &[],
+                &[],
+                &mut Counters::default(),
+            )
+            .duplicates;
+            duplicates.get_mut("react").unwrap().push(
+                PackageValue::new("react", "16.0.0", "./src/unnamed/package.json")
+                    .with_range("^16.0.0"),
+            );
+
+            let mut formatter = DependenciesFormatter::new(duplicates);
+            formatter.set_style(FormatStyles::Full);
+
+            assert_eq!(
+                formatter.format(ColorScheme::None),
+                "react, Unique versions: 3, Spread: 2 major\n\
+                 Locations:\n./src/unnamed/package.json\na\nb\n\n\
+                 Versions:\n16.0.0\n18.0.0\n17.0.0\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_join_compact_locations() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "1.0.0", "./src/2"),
+                    PackageValue::new("test", "2.0.0", "./src/1"),
+                    PackageValue::new("test", "2.0.0", "./src/3"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_compact_locations(true);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "test, Unique versions: 2, Spread: 1 major\nLocations:\n./src/1, ./src/2, ./src/3\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_bucket_files_by_major() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "2.1.0", "./src/1"),
+                    PackageValue::new("test", "2.0.0", "./src/2"),
+                    PackageValue::new("test", "1.4.0", "./src/3"),
+                    PackageValue::new("test", "1.4.0", "./src/4"),
+                    PackageValue::new("test", "1.0.0", "./src/5"),
+                    PackageValue::new("test", "1.2.0", "./src/5")
+                        .with_section(crate::parser::Section::DevDependencies),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Short);
+            formatter.set_by_major(true);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "test, Unique versions: 5, Files: 5, Spread: 1 major\n\
+                 Major versions:\nv2: 2 files\nv1: 3 files\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_annotate_scoped_groups_with_registry() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            for name in ["@acme/ui", "react"] {
+                hash_map.insert(
+                    name.to_string(),
+                    vec![
+                        PackageValue::new(name, "1.0.0", "./src/1"),
+                        PackageValue::new(name, "2.0.0", "./src/2"),
+                    ],
+                );
+            }
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Short);
+            formatter.set_top(Some(2));
+            formatter.set_registries(Some(crate::npmrc::read_npmrc(
+                "./src/fixtures/npmrc/.npmrc",
+            )));
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "@acme/ui, Unique versions: 2, Files: 2, Spread: 1 major, Registry: https://npm.acme.dev/\n\
+                 react, Unique versions: 2, Files: 2, Spread: 1 major\n"
+            );
+        }
+
+        #[test]
+        fn it_should_truncate_long_output() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "1.0.0", "./src/1"),
+                    PackageValue::new("test", "2.0.0", "./src/2"),
+                    PackageValue::new("test", "3.0.0", "./src/3"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_max_lines(Some(3));
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "test, Unique versions: 3, Spread: 2 major\nLocations:\n./src/1\n\
+                 ... and 3 more (use --out to capture all)\n"
+            );
+
+            formatter.set_max_lines(Some(10));
+            assert!(!formatter
+                .format(ColorScheme::None)
+                .contains("more (use --out"));
+
+            formatter.set_max_lines(Some(1));
+            formatter.set_style(FormatStyles::Json);
+            assert!(serde_json::from_str::<serde_json::Value>(
+                &formatter.format(ColorScheme::None)
+            )
+            .is_ok());
+        }
+
+        #[test]
+        fn it_should_count_files_in_short_output() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "2.0.0", "./src/1"),
+                    PackageValue::new("test", "2.0.0", "./src/2"),
+                    PackageValue::new("test", "1.0.0", "./src/3"),
+                    PackageValue::new("test", "1.0.0", "./src/3")
+                        .with_section(crate::parser::Section::DevDependencies),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Short);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "test, Unique versions: 2, Files: 3, Spread: 1 major\n"
+            );
+        }
+
+        #[test]
+        fn it_should_short_format_dependencies() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "1.0.0", "./src/1"),
+                    PackageValue::new("test", "2.0.0", "./src/2"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Short);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                regex::Regex::new("\x1B\\[[0-9;]*m")
+                    .unwrap()
                     .replace_all(&formatted, "")
                     .to_string(),
-                "test, Unique versions: 2\n"
+                "test, Unique versions: 2, Files: 2, Spread: 1 major\n"
             );
         }
 
@@ -183,18 +1533,927 @@ mod test {
 
             let formatter = DependenciesFormatter::new(hash_map);
 
-            let formatted = formatter.format(false);
+            let formatted = formatter.format(ColorScheme::None);
             assert_eq!(
                 regex::Regex::new("\x1B\\[[0-9;]*m")
                     .unwrap()
                     .replace_all(&formatted, "")
                     .to_string(),
-                "test, Unique versions: 2\nLocations:\n./src/1\n./src/2\n\n"
+                "test, Unique versions: 2, Spread: 1 major\nLocations:\n./src/1\n./src/2\n\n"
             );
         }
 
-        mod color {
-            use super::*;
+        #[test]
+        fn it_should_count_unique_versions_only() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "2.0.0", "./src/1"),
+                    PackageValue::new("test", "1.0.0", "./src/2"),
+                    PackageValue::new("test", "1.0.0", "./src/3"),
+                ],
+            );
+
+            let formatter = DependenciesFormatter::new(hash_map);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "test, Unique versions: 2, Spread: 1 major\nLocations:\n./src/1\n./src/2\n./src/3\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_group_dependencies_by_scope() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "lodash".to_string(),
+                vec![
+                    PackageValue::new("lodash", "4.0.0", "./src/1"),
+                    PackageValue::new("lodash", "3.0.0", "./src/2"),
+                ],
+            );
+            hash_map.insert(
+                "@acme/ui".to_string(),
+                vec![
+                    PackageValue::new("@acme/ui", "2.0.0", "./src/1"),
+                    PackageValue::new("@acme/ui", "1.0.0", "./src/2"),
+                ],
+            );
+            hash_map.insert(
+                "@acme/api".to_string(),
+                vec![
+                    PackageValue::new("@acme/api", "2.0.0", "./src/1"),
+                    PackageValue::new("@acme/api", "1.0.0", "./src/2"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Short);
+            formatter.set_group_scope(true);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "Scopes:\n@acme: 2\n(unscoped): 1\n\n\
+                 @acme/api, Unique versions: 2, Files: 2, Spread: 1 major\n\
+                 @acme/ui, Unique versions: 2, Files: 2, Spread: 1 major\n\
+                 lodash, Unique versions: 2, Files: 2, Spread: 1 major\n"
+            );
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn it_should_display_non_utf8_paths() {
+            use std::ffi::OsStr;
+            use std::os::unix::ffi::OsStrExt;
+
+            let path = Path::new(OsStr::from_bytes(b"./caf\xe9/package.json"));
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "1.0.0", path),
+                    PackageValue::new("test", "2.0.0", "./src/2"),
+                ],
+            );
+
+            let formatter = DependenciesFormatter::new(hash_map);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "test, Unique versions: 2, Spread: 1 major\nLocations:\n./caf\u{fffd}/package.json\n./src/2\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_use_forward_slashes_in_paths() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "1.0.0", "C:\\repo\\packages\\a\\package.json"),
+                    PackageValue::new("test", "2.0.0", "D:\\other\\package.json"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_relative_root(Some("C:\\repo".to_string()));
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "test, Unique versions: 2, Spread: 1 major\nLocations:\nD:/other/package.json\npackages/a/package.json\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_normalize_separators_in_sections() {
+            let keys = vec![DuplicateKey {
+                name: "react".to_string(),
+                section: "dependencies".to_string(),
+                path: PathBuf::from(".\\packages\\a\\package.json"),
+            }];
+
+            let formatted = format_duplicate_keys(&keys, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "react, Duplicate key in dependencies: ./packages/a/package.json\n"
+            );
+        }
+
+        #[test]
+        fn it_should_render_paths_relative_to_root() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "1.0.0", "./repo/a/package.json"),
+                    PackageValue::new("test", "2.0.0", "./other/package.json"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_relative_root(Some("./repo/".to_string()));
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "test, Unique versions: 2, Spread: 1 major\nLocations:\n./other/package.json\na/package.json\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_stats() {
+            let mut counters = Counters::default();
+            let files = crate::lookup::get_package_json_files(
+                "./src/fixtures/stats",
+                &[],
+                None,
+                &mut counters,
+            );
+            let total_dependencies =
+                crate::parser::collect_dependencies(&files, &[], &mut counters).len();
+            let duplicates =
+                crate::parser::find_duplicate_dependencies(files.clone(), &[], &[], &mut counters)
+                    .duplicates;
+
+            let mut formatter = DependenciesFormatter::new(duplicates);
+            formatter.set_stats(files.len(), total_dependencies);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "Files scanned: 3\nUnique dependencies: 3\nDuplicated dependencies: 2\nWorst offender: react (3 versions)\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_stats_without_duplicates() {
+            let mut formatter = DependenciesFormatter::new(HashMap::new());
+            formatter.set_stats(1, 1);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "Files scanned: 1\nUnique dependencies: 1\nDuplicated dependencies: 0\nWorst offender: none\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_one_line_per_occurrence() {
+            let mut counters = Counters::default();
+            let files = crate::lookup::get_package_json_files(
+                "./src/fixtures/stats",
+                &[],
+                None,
+                &mut counters,
+            );
+            let duplicates =
+                crate::parser::find_duplicate_dependencies(files, &[], &[], &mut counters)
+                    .duplicates;
+            let occurrences: usize = duplicates.values().map(Vec::len).sum();
+
+            let mut formatter = DependenciesFormatter::new(duplicates);
+            formatter.set_style(FormatStyles::Line);
+
+            let formatted = formatter.format(ColorScheme::Default);
+            assert_eq!(formatted.lines().count(), occurrences);
+            assert!(formatted
+                .lines()
+                .all(|line| line.split('\t').count() == 3 && !line.contains('\x1b')));
+            assert!(formatted.starts_with("lodash\t4.0.0\t./src/fixtures/stats/a/package.json\n"));
+        }
+
+        #[test]
+        fn it_should_format_aligned_table() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "react".to_string(),
+                vec![
+                    PackageValue::new("react", "18.0.0", "./src/1"),
+                    PackageValue::new("react", "17.0.0", "./src/2"),
+                    PackageValue::new("react", "17.0.0", "./src/3"),
+                ],
+            );
+            hash_map.insert(
+                "a".to_string(),
+                vec![
+                    PackageValue::new("a", "2", "./src/1"),
+                    PackageValue::new("a", "1", "./src/2"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Table);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "Package | Versions       | Files\n\
+                 a       | 2, 1           | 2\n\
+                 react   | 18.0.0, 17.0.0 | 3\n"
+            );
+        }
+
+        #[test]
+        fn it_should_count_distinct_files_in_table() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "react".to_string(),
+                vec![
+                    PackageValue::new("react", "18.0.0", "./src/1"),
+                    PackageValue::new("react", "17.0.0", "./src/1")
+                        .with_section(crate::parser::Section::DevDependencies),
+                    PackageValue::new("react", "17.0.0", "./src/2"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Table);
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted.lines().nth(1).unwrap(),
+                "react   | 18.0.0, 17.0.0 | 2"
+            );
+        }
+
+        #[test]
+        fn it_should_truncate_long_table_cells() {
+            let name = "@acme/".to_string() + &"x".repeat(50);
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                name.clone(),
+                vec![
+                    PackageValue::new(&name, "2.0.0", "./src/1"),
+                    PackageValue::new(&name, "1.0.0", "./src/2"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Table);
+
+            let formatted = formatter.format(ColorScheme::None);
+            let row = formatted.lines().nth(1).unwrap();
+            let truncated = format!("@acme/{}…", "x".repeat(33));
+            assert_eq!(row, format!("{} | 2.0.0, 1.0.0 | 2", truncated));
+            assert_eq!(truncated.chars().count(), MAX_COLUMN_WIDTH);
+        }
+
+        #[test]
+        fn it_should_format_top_groups_by_version_count() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            for (name, versions) in [
+                ("a", vec!["2.0.0", "1.0.0"]),
+                ("b", vec!["4.0.0", "3.0.0", "2.0.0", "1.0.0"]),
+                ("c", vec!["3.0.0", "2.0.0", "1.0.0"]),
+                ("d", vec!["3.0.0", "2.0.0", "1.0.0"]),
+            ] {
+                let values = versions
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| PackageValue::new(name, v, format!("./src/{}", i)))
+                    .collect();
+                hash_map.insert(name.to_string(), values);
+            }
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Short);
+            formatter.set_top(Some(2));
+
+            let formatted = formatter.format(ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "b, Unique versions: 4, Files: 4, Spread: 3 major\n\
+                 c, Unique versions: 3, Files: 3, Spread: 2 major\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_yaml() {
+            let mut counters = Counters::default();
+            let files = crate::lookup::get_package_json_files(
+                "./src/fixtures/stats",
+                &[],
+                None,
+                &mut counters,
+            );
+            let duplicates =
+                crate::parser::find_duplicate_dependencies(files, &[], &[], &mut counters)
+                    .duplicates;
+
+            let mut formatter = DependenciesFormatter::new(duplicates);
+            formatter.set_style(FormatStyles::Yaml);
+            formatter.set_max_lines(Some(1));
+
+            let formatted = formatter.format(ColorScheme::Default);
+            let entries: Vec<serde_yaml::Value> = serde_yaml::from_str(&formatted).unwrap();
+            assert_eq!(entries.len(), 2);
+            assert_eq!(entries[0]["name"], "lodash");
+            assert_eq!(
+                entries[0]["versions"],
+                serde_yaml::to_value(["4.0.0", "3.0.0"]).unwrap()
+            );
+            assert_eq!(entries[1]["name"], "react");
+            assert_eq!(entries[1]["paths"].as_sequence().unwrap().len(), 3);
+            assert!(!formatted.contains('\x1B'));
+        }
+
+        #[test]
+        fn it_should_format_dot() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "react".to_string(),
+                vec![
+                    PackageValue::new("react", "18.0.0", "./a/package.json").with_range("^18.0.0"),
+                    PackageValue::new("react", "17.0.0", "./b/package.json").with_range("~17.0.0"),
+                ],
+            );
+            hash_map.insert(
+                "lodash".to_string(),
+                vec![
+                    PackageValue::new("lodash", "4.0.0", "./a/package.json"),
+                    PackageValue::new("lodash", "3.0.0", "./c/package.json"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.try_set_style("dot").unwrap();
+
+            assert_eq!(
+                formatter.format(ColorScheme::Default),
+                "graph nodedup {\n\
+                 \x20 \"lodash\" [shape=box];\n\
+                 \x20 \"react\" [shape=box];\n\
+                 \x20 \"./a/package.json\" [shape=note];\n\
+                 \x20 \"./b/package.json\" [shape=note];\n\
+                 \x20 \"./c/package.json\" [shape=note];\n\
+                 \x20 \"lodash\" -- \"./a/package.json\" [label=\"4.0.0\"];\n\
+                 \x20 \"lodash\" -- \"./c/package.json\" [label=\"3.0.0\"];\n\
+                 \x20 \"react\" -- \"./a/package.json\" [label=\"^18.0.0\"];\n\
+                 \x20 \"react\" -- \"./b/package.json\" [label=\"~17.0.0\"];\n\
+                 }"
+            );
+        }
+
+        #[test]
+        fn it_should_format_junit() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "react".to_string(),
+                vec![
+                    PackageValue::new("react", "18.0.0", "./a&b/package.json")
+                        .with_range(">=18.0.0 <19"),
+                    PackageValue::new("react", "17.0.0", "./<c>/package.json")
+                        .with_range("^17.0.0"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.try_set_style("junit").unwrap();
+
+            assert_eq!(
+                formatter.format(ColorScheme::Default),
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <testsuites>\n\
+                 \x20 <testsuite name=\"nodedup\" tests=\"1\" failures=\"1\" errors=\"0\">\n\
+                 \x20   <testcase classname=\"nodedup\" name=\"react\">\n\
+                 \x20     <failure type=\"duplicate\" message=\"Unique versions: 2, Spread: 1 major\">\
+                 &gt;=18.0.0 &lt;19 ./a&amp;b/package.json\n\
+                 ^17.0.0 ./&lt;c&gt;/package.json</failure>\n\
+                 \x20   </testcase>\n\
+                 \x20 </testsuite>\n\
+                 </testsuites>"
+            );
+        }
+
+        #[test]
+        fn it_should_escape_xml() {
+            assert_eq!(
+                escape_xml(r#"<a href="x">'&'</a>"#),
+                "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+            );
+        }
+
+        #[test]
+        fn it_should_escape_dot_ids() {
+            assert_eq!(dot_id(r#"C:\a "b""#), r#""C:\\a \"b\"""#);
+        }
+
+        #[test]
+        fn it_should_format_nothing_for_none() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "1.0.0", "./src/1"),
+                    PackageValue::new("test", "2.0.0", "./src/2"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.try_set_style("none").unwrap();
+            formatter.set_count_only(true);
+
+            assert_eq!(formatter.format(ColorScheme::Default), "");
+        }
+
+        #[test]
+        fn it_should_format_ndjson() {
+            let mut counters = Counters::default();
+            let files = crate::lookup::get_package_json_files(
+                "./src/fixtures/stats",
+                &[],
+                None,
+                &mut counters,
+            );
+            let duplicates =
+                crate::parser::find_duplicate_dependencies(files, &[], &[], &mut counters)
+                    .duplicates;
+
+            let mut formatter = DependenciesFormatter::new(duplicates);
+            formatter.set_style(FormatStyles::Ndjson);
+            formatter.set_max_lines(Some(1));
+
+            let formatted = formatter.format(ColorScheme::Default);
+            let lines: Vec<serde_json::Value> = formatted
+                .split('\n')
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect();
+            assert_eq!(lines.len(), 2);
+            assert_eq!(lines[0]["name"], "lodash");
+            assert_eq!(lines[0]["versions"], serde_json::json!(["4.0.0", "3.0.0"]));
+            assert_eq!(lines[1]["name"], "react");
+            assert_eq!(lines[1]["paths"].as_array().unwrap().len(), 3);
+        }
+
+        #[test]
+        fn it_should_format_count_only() {
+            let mut counters = Counters::default();
+            let files = crate::lookup::get_package_json_files(
+                "./src/fixtures/stats",
+                &[],
+                None,
+                &mut counters,
+            );
+            let duplicates =
+                crate::parser::find_duplicate_dependencies(files, &[], &[], &mut counters)
+                    .duplicates;
+            let groups = duplicates.len();
+
+            let mut formatter = DependenciesFormatter::new(duplicates);
+            formatter.set_style(FormatStyles::Full);
+            formatter.set_count_only(true);
+
+            let formatted = formatter.format(ColorScheme::Default);
+            assert_eq!(formatted.parse::<usize>().unwrap(), groups);
+            assert_eq!(formatted, "2");
+        }
+
+        #[test]
+        fn it_should_count_every_group_with_top() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            for name in ["a", "b", "c"] {
+                hash_map.insert(
+                    name.to_string(),
+                    vec![
+                        PackageValue::new(name, "2.0.0", "./src/1"),
+                        PackageValue::new(name, "1.0.0", "./src/2"),
+                    ],
+                );
+            }
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_top(Some(1));
+            formatter.set_count_only(true);
+
+            assert_eq!(formatter.format(ColorScheme::None), "3");
+        }
+
+        #[test]
+        fn it_should_format_bundled_mismatches() {
+            let mismatches = vec![BundledMismatch {
+                name: "lodash".to_string(),
+                path: PathBuf::from("./src/1"),
+                version: Some("4.0.0".to_string()),
+                elsewhere: vec![PackageValue::new("lodash", "3.0.0", "./src/2")],
+            }];
+
+            let formatted = format_bundled_mismatches(&mismatches, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "lodash, Bundled in: ./src/1 (4.0.0)\nVersions elsewhere:\n3.0.0 ./src/2\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_types_mismatches() {
+            let mismatches = vec![TypesMismatch {
+                name: "lodash".to_string(),
+                path: PathBuf::from("./src/1"),
+                version: "4.17.0".to_string(),
+                types_version: "3.10.0".to_string(),
+            }];
+
+            let formatted = format_types_mismatches(&mismatches, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "lodash, Types out of line in ./src/1: 4.17.0 with @types 3.10.0\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_non_registry_dependencies() {
+            let values = vec![PackageValue::new("lib", "github:org/lib", "./src/1")
+                .with_kind(crate::parser::VersionKind::NonRegistry)];
+
+            let formatted = format_non_registry_dependencies(&values, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "lib, Not from the registry in ./src/1: github:org/lib\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_version_changes() {
+            let changes = vec![VersionChange {
+                name: "lodash".to_string(),
+                added: vec!["4.17.21".to_string()],
+                removed: vec!["4.17.0".to_string()],
+            }];
+
+            let formatted = format_version_changes(&changes, "main", "feature", ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "lodash, Changed from main to feature:\n- 4.17.0\n+ 4.17.21\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_recommendations() {
+            let mut recommendations = BTreeMap::new();
+            recommendations.insert("react".to_string(), "18.0.0".to_string());
+            recommendations.insert("lodash".to_string(), "4.0.0".to_string());
+
+            let formatted = format_recommendations(&recommendations, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "Recommended:\nlodash -> 4.0.0\nreact -> 18.0.0\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_same_version_groups() {
+            let groups = vec![SameVersionGroup {
+                name: "react".to_string(),
+                range: "^18.0.0".to_string(),
+                files: 3,
+            }];
+
+            let formatted = format_same_version_groups(&groups, ColorScheme::None);
+            assert_eq!(formatted, "react, Same version ^18.0.0 in 3 files\n");
+        }
+
+        #[test]
+        fn it_should_format_policy_violations() {
+            let violations = vec![PolicyViolation {
+                name: "react".to_string(),
+                path: PathBuf::from("./b/package.json"),
+                range: "^17.0.0".to_string(),
+                approved: "^18.2.0".to_string(),
+            }];
+
+            let formatted = format_policy_violations(&violations, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "react, Approved ^18.2.0 but declared ^17.0.0 in ./b/package.json\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_ignored_with_reasons() {
+            let entries = vec![
+                IgnoreEntry {
+                    pattern: "lodash".to_string(),
+                    reason: Some("migrating in Q3".to_string()),
+                },
+                IgnoreEntry {
+                    pattern: "@babel/*".to_string(),
+                    reason: None,
+                },
+            ];
+
+            let formatted = format_ignored(&entries, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "lodash, Ignored: migrating in Q3\n@babel/*, Ignored\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_lock_violations() {
+            let violations = vec![LockViolation {
+                name: "lodash".to_string(),
+                path: PathBuf::from("./b/package.json"),
+                range: "^3.10.0".to_string(),
+                locked: "4.0.0".to_string(),
+            }];
+
+            let formatted = format_lock_violations(&violations, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "lodash, Declared ^3.10.0 in ./b/package.json but locked at 4.0.0\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_phantom_dependencies() {
+            let phantoms = vec![PhantomDependency {
+                name: "lodash".to_string(),
+                path: PathBuf::from("./b/package.json"),
+                source: PathBuf::from("./b/src/app.ts"),
+            }];
+
+            let formatted = format_phantom_dependencies(&phantoms, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "lodash, Imported but not declared in ./b/package.json: ./b/src/app.ts\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_duplicate_keys() {
+            let keys = vec![DuplicateKey {
+                name: "react".to_string(),
+                section: "dependencies".to_string(),
+                path: PathBuf::from("./src/1"),
+            }];
+
+            let formatted = format_duplicate_keys(&keys, ColorScheme::None);
+            assert_eq!(formatted, "react, Duplicate key in dependencies: ./src/1\n");
+        }
+
+        #[test]
+        fn it_should_format_case_conflicts() {
+            let mut conflicts: BTreeMap<String, Vec<String>> = BTreeMap::new();
+            conflicts.insert(
+                "react".to_string(),
+                vec!["React".to_string(), "react".to_string()],
+            );
+
+            let formatted = format_case_conflicts(&conflicts, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "react, Declared with different casing: React, react\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_case_collisions() {
+            let collisions = vec![CaseCollision {
+                path: PathBuf::from("./a/package.json"),
+                names: vec!["Lodash".to_string(), "lodash".to_string()],
+            }];
+
+            let formatted = format_case_collisions(&collisions, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "Lodash, lodash, Declared with different casing in ./a/package.json\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_resolution_drift() {
+            let mut drift: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            drift.insert(
+                "lodash".to_string(),
+                vec![
+                    PackageValue::new("lodash", "4.17.21", "packages/a"),
+                    PackageValue::new("lodash", "4.0.0", "packages/b"),
+                ],
+            );
+
+            let formatted = format_resolution_drift(&drift, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "lodash, Resolved versions: 2\n4.17.21 packages/a\n4.0.0 packages/b\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_edits() {
+            let edits = vec![Edit {
+                path: PathBuf::from("./src/2"),
+                section: crate::parser::Section::Dependencies,
+                name: "lodash".to_string(),
+                from: "^3.0.0".to_string(),
+                to: "^4.0.0".to_string(),
+            }];
+
+            let formatted = format_edits(&edits, false, ColorScheme::None);
+            assert_eq!(formatted, "Updated:\n./src/2: lodash ^3.0.0 -> ^4.0.0\n");
+
+            let formatted = format_edits(&edits, true, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "Would update:\n./src/2: lodash ^3.0.0 -> ^4.0.0\n"
+            );
+        }
+
+        #[test]
+        fn it_should_write_report_creating_folders() {
+            let dir = std::env::temp_dir().join("nodedup-report-test");
+            let path = dir.join("reports/duplicates.json");
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "test".to_string(),
+                vec![
+                    PackageValue::new("test", "1.0.0", "./src/1"),
+                    PackageValue::new("test", "2.0.0", "./src/2"),
+                ],
+            );
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Json);
+            let report = format!("{}\n", formatter.format(ColorScheme::None));
+
+            write_report(path.to_str().unwrap(), &report);
+
+            assert_eq!(fs::read_to_string(&path).unwrap(), report);
+            assert!(report.starts_with("{\"count\":1,"));
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn it_should_format_workspace_protocol_mixes() {
+            let mut mixes: BTreeMap<String, Vec<PackageValue>> = BTreeMap::new();
+            mixes.insert(
+                "@acme/lib".to_string(),
+                vec![
+                    PackageValue::new("@acme/lib", "1.0.0", "./src/1").with_range("^1.0.0"),
+                    PackageValue::new("@acme/lib", "", "./src/2").with_range("workspace:*"),
+                ],
+            );
+
+            let formatted = format_workspace_protocol_mixes(&mixes, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "@acme/lib, Referenced both as a workspace and from the registry:\n\
+                 ^1.0.0 ./src/1\nworkspace:* ./src/2\n\n"
+            );
+        }
+
+        #[test]
+        fn it_should_format_resolved_duplicates() {
+            let names = vec!["lodash".to_string(), "react".to_string()];
+
+            let formatted = format_resolved_duplicates(&names, ColorScheme::None);
+            assert_eq!(formatted, "Resolved since baseline:\nlodash\nreact\n");
+        }
+
+        #[test]
+        fn it_should_format_toml() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "@acme/test".to_string(),
+                vec![
+                    PackageValue::new("@acme/test", "2.0.0", "./src/1"),
+                    PackageValue::new("@acme/test", "1.0.0", "./src/2"),
+                ],
+            );
+
+            let mut formatter = DependenciesFormatter::new(hash_map);
+            formatter.set_style(FormatStyles::Toml);
+
+            let formatted = formatter.format(ColorScheme::Default);
+            let parsed: toml::Table = toml::from_str(&formatted).unwrap();
+            let group = parsed["@acme/test"].as_table().unwrap();
+            assert_eq!(
+                group["versions"],
+                toml::Value::Array(vec!["2.0.0".into(), "1.0.0".into()])
+            );
+            assert_eq!(
+                group["paths"],
+                toml::Value::Array(vec!["./src/1".into(), "./src/2".into()])
+            );
+        }
+
+        #[test]
+        fn it_should_format_missing_dependencies() {
+            let mut missing: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+            missing.insert(
+                "lodash".to_string(),
+                vec![PathBuf::from("./src/1"), PathBuf::from("./src/2")],
+            );
+            missing.insert("react".to_string(), vec![]);
+
+            let formatted = format_missing_dependencies(&missing, ColorScheme::None);
+            assert_eq!(
+                formatted,
+                "lodash, Missing in 2 files:\n./src/1\n./src/2\n\n"
+            );
+        }
+
+        mod json {
+            use super::*;
+
+            fn formatter() -> DependenciesFormatter<Ready> {
+                let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+                hash_map.insert(
+                    "test".to_string(),
+                    vec![
+                        PackageValue::new("test", "2.0.0", "./src/1"),
+                        PackageValue::new("test", "1.0.0", "./src/2"),
+                    ],
+                );
+
+                let mut formatter = DependenciesFormatter::new(hash_map);
+                formatter.set_style(FormatStyles::Json);
+                formatter
+            }
+
+            #[test]
+            fn it_should_format_compact_json() {
+                let formatted = formatter().format(ColorScheme::Default);
+                assert_eq!(
+                    formatted,
+                    r#"{"count":1,"duplicates":[{"name":"test","spread":"1 major","versions":["2.0.0","1.0.0"],"paths":["./src/1","./src/2"]}]}"#
+                );
+            }
+
+            #[test]
+            fn it_should_format_valid_json_without_duplicates() {
+                let mut formatter = DependenciesFormatter::new(HashMap::new());
+                formatter.set_style(FormatStyles::Json);
+
+                let formatted = formatter.format(ColorScheme::Default);
+                let parsed: serde_json::Value = serde_json::from_str(&formatted).unwrap();
+                assert_eq!(parsed["count"], 0);
+                assert_eq!(parsed["duplicates"], serde_json::json!([]));
+            }
+
+            #[test]
+            fn it_should_format_pretty_json() {
+                let mut formatter = formatter();
+                formatter.set_json_pretty(true);
+
+                let pretty = formatter.format(ColorScheme::Default);
+                let compact = self::formatter().format(ColorScheme::Default);
+                assert!(pretty.contains("\n  "));
+                assert_eq!(
+                    serde_json::from_str::<serde_json::Value>(&pretty).unwrap(),
+                    serde_json::from_str::<serde_json::Value>(&compact).unwrap()
+                );
+            }
+        }
+
+        mod color {
+            use super::*;
+
+            #[test]
+            fn it_should_use_different_colors_per_scheme() {
+                let codes = |colors: ColorScheme| {
+                    let highlight = colors.highlight("react");
+                    let heading = colors.heading("Locations:");
+                    (
+                        highlight.fgcolor().map(|c| c.to_fg_str().to_string()),
+                        heading.fgcolor().map(|c| c.to_fg_str().to_string()),
+                    )
+                };
+
+                assert_eq!(
+                    codes(ColorScheme::Default),
+                    (Some("31".to_string()), Some("32".to_string()))
+                );
+                assert_eq!(
+                    codes(ColorScheme::HighContrast),
+                    (Some("93".to_string()), Some("96".to_string()))
+                );
+                assert!(ColorScheme::None.highlight("react").is_plain());
+                assert!(ColorScheme::None.heading("Locations:").is_plain());
+            }
 
             #[test]
             #[ignore] // Not working on CI
@@ -210,10 +2469,10 @@ mod test {
 
                 let formatter = DependenciesFormatter::new(hash_map);
 
-                let formatted = formatter.format(true);
+                let formatted = formatter.format(ColorScheme::Default);
                 assert_ne!(
                     formatted,
-                    "test, Unique versions: 2\nLocations:\n./src/1\n./src/2\n\n"
+                    "test, Unique versions: 2, Spread: 1 major\nLocations:\n./src/1\n./src/2\n\n"
                 );
             }
 
@@ -230,10 +2489,10 @@ mod test {
 
                 let formatter = DependenciesFormatter::new(hash_map);
 
-                let formatted = formatter.format(false);
+                let formatted = formatter.format(ColorScheme::None);
                 assert_eq!(
                     formatted,
-                    "test, Unique versions: 2\nLocations:\n./src/1\n./src/2\n\n"
+                    "test, Unique versions: 2, Spread: 1 major\nLocations:\n./src/1\n./src/2\n\n"
                 );
             }
         }
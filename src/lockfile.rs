@@ -0,0 +1,242 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use semver::Version;
+use serde_json::{Map, Value};
+
+use crate::compat::to_version_req;
+use crate::parser::{
+    count_versions, is_ignored, process_dependency, PackageValue, Section, VersionKind,
+};
+
+const ROOT: &str = ".";
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct LockViolation {
+    pub name: String,
+    pub path: PathBuf,
+    pub range: String,
+    pub locked: String,
+}
+
+fn read_lockfile(path: &str) -> Value {
+    let content = fs::read_to_string(path).unwrap_or_else(|_| {
+        panic!("Failed to read the lockfile: {}", path);
+    });
+    serde_json::from_str(&content).unwrap_or_else(|_| {
+        panic!("Failed to parse the lockfile: {}", path);
+    })
+}
+
+pub fn find_resolution_drift(path: &str, ignores: &[String]) -> HashMap<String, Vec<PackageValue>> {
+    let lockfile = read_lockfile(path);
+
+    let mut hash_map = resolve_workspaces(&lockfile);
+    hash_map.retain(|name, values| count_versions(values) > 1 && !is_ignored(name, ignores));
+
+    hash_map
+}
+
+fn resolve_workspaces(lockfile: &Value) -> HashMap<String, Vec<PackageValue>> {
+    let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+    let Some(packages) = lockfile.get("packages").and_then(|p| p.as_object()) else {
+        return hash_map;
+    };
+
+    for (location, package) in packages.iter().filter(|(l, _)| is_workspace(l)) {
+        for (key, section) in [
+            ("dependencies", Section::Dependencies),
+            ("devDependencies", Section::DevDependencies),
+        ] {
+            let names = package
+                .get(key)
+                .and_then(|d| d.as_object())
+                .into_iter()
+                .flat_map(|d| d.keys());
+            for name in names {
+                if let Some(version) = resolve_version(packages, location, name) {
+                    let workspace = if location.is_empty() { ROOT } else { location };
+                    process_dependency(name, version, &mut hash_map, Path::new(workspace), section);
+                }
+            }
+        }
+    }
+
+    hash_map
+}
+
+// Each package.json is looked up in the lockfile by its folder relative to the lockfile, and its
+// declared range must be satisfied by the version installed for that workspace
+pub fn find_lock_violations(
+    path: &str,
+    dependencies: &HashMap<String, Vec<PackageValue>>,
+) -> Vec<LockViolation> {
+    let lockfile = read_lockfile(path);
+    let Some(packages) = lockfile.get("packages").and_then(|p| p.as_object()) else {
+        return vec![];
+    };
+    let root = match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new(ROOT),
+    };
+    let Ok(root) = root.canonicalize() else {
+        return vec![];
+    };
+
+    let mut violations: Vec<LockViolation> = dependencies
+        .values()
+        .flatten()
+        .filter(|v| v.kind != VersionKind::NonRegistry)
+        .filter_map(|v| {
+            let location = get_location(&root, &v.path)?;
+            let locked = resolve_version(packages, &location, &v.name)?;
+            let satisfied = to_version_req(&v.range)?.matches(&Version::parse(locked).ok()?);
+            (!satisfied).then(|| LockViolation {
+                name: v.name.clone(),
+                path: v.path.clone(),
+                range: v.range.clone(),
+                locked: locked.to_string(),
+            })
+        })
+        .collect();
+    violations.sort_by(|a, b| (&a.name, &a.path).cmp(&(&b.name, &b.path)));
+
+    violations
+}
+
+// package.json files outside the lockfile's folder belong to another install and are skipped
+fn get_location(root: &Path, manifest: &Path) -> Option<String> {
+    let folder = manifest.parent()?.canonicalize().ok()?;
+    let relative = folder.strip_prefix(root).ok()?;
+
+    Some(
+        relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+    )
+}
+
+fn is_workspace(location: &str) -> bool {
+    !location.split('/').any(|part| part == "node_modules")
+}
+
+// Mirrors node's lookup: the nearest node_modules folder walking up from the workspace wins
+fn resolve_version<'a>(
+    packages: &'a Map<String, Value>,
+    location: &str,
+    name: &str,
+) -> Option<&'a str> {
+    let mut base = location;
+    loop {
+        let key = if base.is_empty() {
+            format!("node_modules/{}", name)
+        } else {
+            format!("{}/node_modules/{}", base, name)
+        };
+        if let Some(version) = packages
+            .get(&key)
+            .and_then(|p| p.get("version"))
+            .and_then(|v| v.as_str())
+        {
+            return Some(version);
+        }
+        if base.is_empty() {
+            return None;
+        }
+        base = base
+            .rsplit_once('/')
+            .map(|(parent, _)| parent)
+            .unwrap_or("");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCKFILE: &str = "./src/fixtures/lockfile/package-lock.json";
+
+    #[test]
+    fn it_should_report_workspaces_resolving_different_versions() {
+        let result = find_resolution_drift(LOCKFILE, &[]);
+
+        let mut expected: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        expected.insert(
+            "lodash".to_string(),
+            vec![
+                PackageValue::new("lodash", "4.17.21", "packages/a"),
+                PackageValue::new("lodash", "4.0.0", "packages/b"),
+            ],
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn it_should_respect_ignores() {
+        let result = find_resolution_drift(LOCKFILE, &["lodash".to_string()]);
+
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn it_should_resolve_from_nearest_node_modules() {
+        let lockfile: Value = serde_json::from_str(&fs::read_to_string(LOCKFILE).unwrap()).unwrap();
+        let packages = lockfile.get("packages").unwrap().as_object().unwrap();
+
+        assert_eq!(
+            resolve_version(packages, "packages/a", "lodash"),
+            Some("4.17.21")
+        );
+        assert_eq!(
+            resolve_version(packages, "packages/b", "lodash"),
+            Some("4.0.0")
+        );
+        assert_eq!(resolve_version(packages, "", "react"), Some("18.2.0"));
+        assert_eq!(resolve_version(packages, "packages/b", "missing"), None);
+    }
+
+    #[test]
+    fn it_should_skip_installed_packages() {
+        assert!(is_workspace("packages/a"));
+        assert!(is_workspace(""));
+        assert!(!is_workspace("node_modules/lodash"));
+        assert!(!is_workspace("packages/a/node_modules/lodash"));
+    }
+
+    #[test]
+    fn it_should_report_ranges_not_satisfied_by_locked_versions() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/lockfile/packages/a/package.json"),
+            PathBuf::from("./src/fixtures/lockfile/packages/b/package.json"),
+            PathBuf::from("./src/fixtures/stats/a/package.json"),
+        ];
+        let dependencies = crate::parser::collect_dependencies(
+            &paths,
+            &[],
+            &mut crate::diagnostics::Counters::default(),
+        );
+
+        let violations = find_lock_violations(LOCKFILE, &dependencies);
+
+        assert_eq!(
+            violations,
+            vec![LockViolation {
+                name: "lodash".to_string(),
+                path: PathBuf::from("./src/fixtures/lockfile/packages/b/package.json"),
+                range: "^3.10.0".to_string(),
+                locked: "4.0.0".to_string(),
+            }]
+        );
+    }
+
+    #[should_panic]
+    #[test]
+    fn it_should_panic_for_missing_lockfile() {
+        find_resolution_drift("./src/fixtures/lockfile/missing.json", &[]);
+    }
+}
@@ -1,20 +1,42 @@
 use std::{
-    env,
-    path::{Component, Path},
+    collections::HashMap,
+    env, fs,
+    io::ErrorKind,
+    path::{Component, Path, PathBuf},
+    process::Command,
 };
 
+use glob::{glob, Pattern};
 use walkdir::{DirEntry, WalkDir};
 
-pub fn get_package_json_files(dir_path: &str, ignores: &[String]) -> Vec<String> {
+use crate::{deno, diagnostics::Counters};
+
+// With a depth, package.json files in folders that many levels below dir_path or deeper are
+// not walked into at all
+pub fn get_package_json_files(
+    dir_path: &str,
+    ignores: &[String],
+    ignore_below_depth: Option<usize>,
+    counters: &mut Counters,
+) -> Vec<PathBuf> {
     match env::current_dir() {
-        Ok(path) => println!("Call directory is: {}", path.display()),
-        Err(e) => println!("Error getting call directory: {}", e),
+        Ok(path) => log::info!("Call directory is: {}", path.display()),
+        Err(e) => log::error!("Error getting call directory: {}", e),
     }
     let absolute_path = Path::new(dir_path).canonicalize().unwrap_or_else(|_| {
         panic!("Failed to resolve the path: {}", dir_path);
     });
-    println!("Scanning directory: {}", absolute_path.display());
-    WalkDir::new(dir_path)
+    if absolute_path.is_file() {
+        if is_manifest_name(&absolute_path) {
+            log::info!("Scanning file: {}", absolute_path.display());
+            counters.discovered += 1;
+            return vec![PathBuf::from(dir_path)];
+        }
+        panic!("Not a package.json or deno.json file: {}", dir_path);
+    }
+    log::info!("Scanning directory: {}", absolute_path.display());
+    let files = WalkDir::new(dir_path)
+        .max_depth(ignore_below_depth.unwrap_or(usize::MAX))
         .into_iter()
         .filter_entry(|e: &DirEntry| {
             !is_node_modules_path(e.path())
@@ -22,17 +44,231 @@ pub fn get_package_json_files(dir_path: &str, ignores: &[String]) -> Vec<String>
                     .iter()
                     .any(|i| i.contains('/') && e.path().to_string_lossy().contains(i))
         })
-        .filter_map(|e| e.ok()) // This now correctly operates on the result of into_iter(), which is an Iterator.
+        .filter_map(|e| e.map_err(|e| record_walk_error(e, counters)).ok())
         .filter(|e| e.file_type().is_file())
-        .filter_map(|e| {
-            let path = e.path();
-            if path.file_name()? == "package.json" {
-                path.to_str().map(String::from)
-            } else {
-                None
+        .filter(|e| is_manifest_name(e.path()))
+        .map(DirEntry::into_path)
+        .collect::<Vec<PathBuf>>();
+    counters.discovered += files.len();
+
+    let files = dedupe_canonical_paths(files);
+    counters.skipped += counters.discovered - files.len();
+
+    files
+}
+
+// deno.json files declare their dependencies as versioned imports and are scanned alongside
+pub fn is_manifest_name(path: &Path) -> bool {
+    path.file_name().is_some_and(|n| n == "package.json") || deno::is_deno_manifest(path)
+}
+
+// An unreadable folder hides its whole subtree, so every one is counted and named under --verbose
+fn record_walk_error(error: walkdir::Error, counters: &mut Counters) {
+    counters.unreadable += 1;
+    let path = error.path().unwrap_or(Path::new("")).display().to_string();
+    match error.io_error().map(std::io::Error::kind) {
+        Some(ErrorKind::PermissionDenied) => log::info!("Skipping {}: permission denied", path),
+        _ => log::info!("Skipping {}: {}", path, error),
+    }
+}
+
+pub fn get_workspace_package_json_files(dir_path: &str, counters: &mut Counters) -> Vec<PathBuf> {
+    let root = Path::new(dir_path);
+    let patterns = read_workspace_patterns(root).unwrap_or_else(|| {
+        panic!("No workspaces declared in: {}", dir_path);
+    });
+    let (excluded, included): (Vec<&String>, Vec<&String>) =
+        patterns.iter().partition(|p| p.starts_with('!'));
+    let excluded: Vec<Pattern> = excluded
+        .iter()
+        .filter_map(|p| Pattern::new(p.trim_start_matches('!')).ok())
+        .collect();
+
+    let base: PathBuf = root
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+    let mut files: Vec<PathBuf> = included
+        .iter()
+        .filter_map(|pattern| glob(&root.join(pattern).join("package.json").to_string_lossy()).ok())
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter(|path| !is_node_modules_path(path))
+        // glob drops the leading `./`, the matches are rebased so paths look like the walk's
+        .filter_map(|path| Some(root.join(path.strip_prefix(&base).ok()?)))
+        .filter(|path| {
+            let workspace = path
+                .parent()
+                .and_then(|p| p.strip_prefix(root).ok())
+                .unwrap_or(path);
+            !excluded.iter().any(|p| p.matches_path(workspace))
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+    let manifest = root.join("package.json");
+    if manifest.is_file() {
+        files.insert(0, manifest);
+    }
+    counters.discovered += files.len();
+
+    let files = dedupe_canonical_paths(files);
+    counters.skipped += counters.discovered - files.len();
+
+    files
+}
+
+// npm and yarn list the globs in package.json, pnpm keeps them in its own file
+fn read_workspace_patterns(root: &Path) -> Option<Vec<String>> {
+    let from_manifest = fs::read_to_string(root.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .and_then(|manifest| {
+            let workspaces = manifest.get("workspaces")?;
+            let patterns = workspaces.get("packages").unwrap_or(workspaces);
+            serde_json::from_value::<Vec<String>>(patterns.clone()).ok()
+        });
+
+    from_manifest.or_else(|| {
+        let content = fs::read_to_string(root.join("pnpm-workspace.yaml")).ok()?;
+        let workspace: serde_yaml::Value = serde_yaml::from_str(&content).ok()?;
+        serde_yaml::from_value(workspace.get("packages")?.clone()).ok()
+    })
+}
+
+// Paths are compared component by component, so `packages/ui` does not match `packages/ui-kit`
+pub fn filter_by_path_prefix(files: Vec<PathBuf>, root: &str, prefix: &str) -> Vec<PathBuf> {
+    let prefix = Path::new(prefix);
+    let prefix = prefix.strip_prefix(".").unwrap_or(prefix);
+    let files: Vec<PathBuf> = files
+        .into_iter()
+        .filter(|file| {
+            let relative = file.strip_prefix(root).unwrap_or(file);
+            relative
+                .strip_prefix(".")
+                .unwrap_or(relative)
+                .starts_with(prefix)
+        })
+        .collect();
+    log::info!("Kept {} files under {}", files.len(), prefix.display());
+
+    files
+}
+
+// Paths are printed relative to the scanned folder and joined back onto it, so they line up
+// with the paths found by the walk
+pub fn get_changed_files(dir_path: &str, since: &str) -> Option<Vec<PathBuf>> {
+    let output = Command::new("git")
+        .args(["-C", dir_path, "diff", "--name-only", "--relative", since])
+        .output();
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            let error = String::from_utf8_lossy(&output.stderr);
+            log::warn!(
+                "Scanning every workspace: git diff failed: {}",
+                error.trim()
+            );
+            return None;
+        }
+        Err(e) => {
+            log::warn!("Scanning every workspace: git is not available: {}", e);
+            return None;
+        }
+    };
+
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| Path::new(dir_path).join(line))
+            .collect(),
+    )
+}
+
+// Each changed file belongs to the package.json in its nearest enclosing folder
+pub fn filter_changed_workspaces(files: Vec<PathBuf>, changed: &[PathBuf]) -> Vec<PathBuf> {
+    let owners: Vec<&Path> = changed
+        .iter()
+        .filter_map(|changed| {
+            files
+                .iter()
+                .filter_map(|file| file.parent())
+                .filter(|dir| changed.starts_with(dir))
+                .max_by_key(|dir| dir.components().count())
+        })
+        .collect();
+    let files: Vec<PathBuf> = files
+        .iter()
+        .filter(|file| file.parent().is_some_and(|dir| owners.contains(&dir)))
+        .cloned()
+        .collect();
+    log::info!("Kept {} changed workspaces", files.len());
+
+    files
+}
+
+// One path per line, the format shared by --list-files and manifest files
+pub fn format_file_list(files: &[PathBuf]) -> String {
+    files
+        .iter()
+        .map(|file| format!("{}\n", file.display()))
+        .collect()
+}
+
+pub fn write_manifest(path: &str, files: &[PathBuf]) {
+    let content = format_file_list(files);
+    fs::write(path, content).unwrap_or_else(|_| {
+        panic!("Failed to write the manifest: {}", path);
+    });
+}
+
+pub fn read_manifest(path: &str, counters: &mut Counters) -> Vec<PathBuf> {
+    let content = fs::read_to_string(path).unwrap_or_else(|_| {
+        panic!("Failed to read the manifest: {}", path);
+    });
+    let listed: Vec<&str> = content.lines().filter(|l| !l.trim().is_empty()).collect();
+    counters.discovered += listed.len();
+
+    let files: Vec<PathBuf> = listed
+        .into_iter()
+        .filter(|file| {
+            let exists = Path::new(file).is_file();
+            if !exists {
+                log::warn!("Skipping {}: listed in the manifest but not found", file);
             }
+            exists
         })
-        .collect::<Vec<String>>()
+        .map(PathBuf::from)
+        .collect();
+    counters.skipped += counters.discovered - files.len();
+
+    files
+}
+
+fn dedupe_canonical_paths(files: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen: HashMap<PathBuf, PathBuf> = HashMap::new();
+    files
+        .into_iter()
+        .filter(|file| {
+            let Ok(canonical) = file.canonicalize() else {
+                return true;
+            };
+            match seen.get(&canonical) {
+                Some(original) => {
+                    log::warn!(
+                        "Skipping {}: same file as {}",
+                        file.display(),
+                        original.display()
+                    );
+                    false
+                }
+                None => {
+                    seen.insert(canonical, file.clone());
+                    true
+                }
+            }
+        })
+        .collect()
 }
 
 fn is_node_modules_path(path: &Path) -> bool {
@@ -40,39 +276,258 @@ fn is_node_modules_path(path: &Path) -> bool {
         .any(|c| matches!(c, Component::Normal(os_str) if os_str == "node_modules"))
 }
 
+// Paths stay relative to the call directory so the report reads the same as without the flag
+pub fn resolve_repo_root(dir_path: &str) -> String {
+    let absolute_path = Path::new(dir_path).canonicalize().unwrap_or_else(|_| {
+        panic!("Failed to resolve the path: {}", dir_path);
+    });
+    let Some(root) = find_repo_root(&absolute_path) else {
+        log::warn!("No repository root found above {}", dir_path);
+        return dir_path.to_string();
+    };
+    log::info!("Repository root is: {}", root.display());
+
+    env::current_dir()
+        .ok()
+        .and_then(|cwd| pathdiff::diff_paths(root, cwd))
+        .map(|relative| {
+            if relative.as_os_str().is_empty() {
+                ".".to_string()
+            } else {
+                relative.display().to_string()
+            }
+        })
+        .unwrap_or_else(|| root.display().to_string())
+}
+
+fn find_repo_root(dir: &Path) -> Option<&Path> {
+    dir.ancestors()
+        .find(|dir| dir.join(".git").exists() || declares_workspaces(&dir.join("package.json")))
+}
+
+fn declares_workspaces(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+        .is_some_and(|manifest| manifest.get("workspaces").is_some())
+}
+
+pub fn resolve_ignore_file(dir_path: &str, ignore_file: Option<&str>) -> Option<String> {
+    match ignore_file {
+        Some(path) if Path::new(path).is_file() => Some(path.to_string()),
+        Some(path) => panic!("Failed to resolve the ignore file: {}", path),
+        None => get_ignore_file(dir_path),
+    }
+}
+
 pub fn get_ignore_file(dir_path: &str) -> Option<String> {
+    find_nearest_file(dir_path, ".ndignore")
+}
+
+pub fn get_npmrc_file(dir_path: &str) -> Option<String> {
+    find_nearest_file(dir_path, ".npmrc")
+}
+
+fn find_nearest_file(dir_path: &str, file_name: &str) -> Option<String> {
     let absolute_path = Path::new(dir_path).canonicalize().unwrap_or_else(|_| {
         panic!("Failed to resolve the path: {}", dir_path);
     });
-    let ignore_file_path = absolute_path.join(".ndignore");
 
-    if ignore_file_path.exists() {
-        ignore_file_path.to_str().map(String::from)
-    } else {
-        None
+    // Nearest file wins, the same way git discovers its config. The search stops at the
+    // repository boundary so an unrelated file higher up is never picked up.
+    for dir in absolute_path.ancestors() {
+        let file_path = dir.join(file_name);
+        if file_path.exists() {
+            return file_path.to_str().map(String::from);
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
     }
+
+    None
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_logger;
 
     #[test]
     fn it_should_return_list_of_package_json_files() {
-        let files = get_package_json_files("./src/data/", &[]);
+        let files = get_package_json_files("./src/data/", &[], None, &mut Counters::default());
         assert_eq!(files.len(), 1);
     }
 
+    #[test]
+    fn it_should_accept_package_json_file() {
+        let files = get_package_json_files(
+            "./src/data/package.json",
+            &[],
+            None,
+            &mut Counters::default(),
+        );
+        assert_eq!(files, vec![PathBuf::from("./src/data/package.json")]);
+    }
+
+    #[should_panic]
+    #[test]
+    fn it_should_panic_for_other_files() {
+        get_package_json_files("./src/data/.ndignore", &[], None, &mut Counters::default());
+    }
+
     #[should_panic]
     #[test]
     fn it_should_panic_for_empty_path() {
-        get_package_json_files("", &[]);
+        get_package_json_files("", &[], None, &mut Counters::default());
     }
 
     #[should_panic]
     #[test]
     fn it_should_panic() {
-        get_package_json_files("./.../..", &[]);
+        get_package_json_files("./.../..", &[], None, &mut Counters::default());
+    }
+
+    #[test]
+    fn it_should_skip_second_path_to_same_file() {
+        let files = dedupe_canonical_paths(vec![
+            PathBuf::from("./src/data/package.json"),
+            PathBuf::from("./src/data/../data/package.json"),
+            PathBuf::from("./src/fixtures/stats/a/package.json"),
+        ]);
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("./src/data/package.json"),
+                PathBuf::from("./src/fixtures/stats/a/package.json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_warn_about_skipped_file() {
+        test_logger::init();
+
+        dedupe_canonical_paths(vec![
+            PathBuf::from("./src/data/package.json"),
+            PathBuf::from("./src/data/../data/package.json"),
+        ]);
+
+        assert!(test_logger::contains(
+            log::Level::Warn,
+            "Skipping ./src/data/../data/package.json: same file as ./src/data/package.json"
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_should_count_unreadable_folders() {
+        use std::os::unix::fs::PermissionsExt;
+
+        test_logger::init();
+        let dir = env::temp_dir().join("nodedup-unreadable-test");
+        let locked = dir.join("locked");
+        fs::create_dir_all(&locked).unwrap();
+        fs::write(dir.join("package.json"), "{}").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        let dir_path = dir.to_str().unwrap();
+
+        // Permissions are not enforced for root, so there is nothing to simulate there
+        let enforced = fs::read_dir(&locked).is_err();
+        let mut counters = Counters::default();
+        let files = get_package_json_files(dir_path, &[], None, &mut counters);
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files, vec![dir.join("package.json")]);
+        if enforced {
+            assert_eq!(counters.unreadable, 1);
+            assert!(test_logger::contains(
+                log::Level::Info,
+                &format!("Skipping {}: permission denied", locked.display())
+            ));
+        }
+    }
+
+    #[test]
+    fn it_should_skip_files_below_depth() {
+        let root = env::temp_dir().join("nodedup-depth-test");
+        let deep = root.join("examples/legacy/app");
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(root.join("package.json"), "{}").unwrap();
+        fs::write(root.join("examples/package.json"), "{}").unwrap();
+        fs::write(root.join("examples/legacy/package.json"), "{}").unwrap();
+        fs::write(deep.join("package.json"), "{}").unwrap();
+        let root_path = root.to_str().unwrap();
+
+        let mut files = get_package_json_files(root_path, &[], Some(2), &mut Counters::default());
+        files.sort();
+
+        assert_eq!(
+            files,
+            vec![
+                root.join("examples/package.json"),
+                root.join("package.json")
+            ]
+        );
+        assert_eq!(
+            get_package_json_files(root_path, &[], None, &mut Counters::default()).len(),
+            4
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn it_should_list_scanned_files() {
+        let files =
+            get_package_json_files("./src/fixtures/stats", &[], None, &mut Counters::default());
+
+        let list = format_file_list(&files);
+        let mut listed: Vec<&str> = list.lines().collect();
+        listed.sort();
+
+        assert!(list.ends_with('\n'));
+        assert_eq!(
+            listed,
+            vec![
+                "./src/fixtures/stats/a/package.json",
+                "./src/fixtures/stats/b/package.json",
+                "./src/fixtures/stats/c/package.json",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_round_trip_manifest() {
+        let path = env::temp_dir().join("nodedup-manifest-test.txt");
+        let path = path.to_str().unwrap();
+        let files =
+            get_package_json_files("./src/fixtures/stats", &[], None, &mut Counters::default());
+
+        write_manifest(path, &files);
+
+        assert_eq!(read_manifest(path, &mut Counters::default()), files);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_should_drop_stale_manifest_entries() {
+        test_logger::init();
+        let path = env::temp_dir().join("nodedup-stale-manifest-test.txt");
+        let path = path.to_str().unwrap();
+        let stale = PathBuf::from("./src/fixtures/removed/package.json");
+        write_manifest(path, &[PathBuf::from("./src/data/package.json"), stale]);
+        let mut counters = Counters::default();
+
+        let files = read_manifest(path, &mut counters);
+
+        assert_eq!(files, vec![PathBuf::from("./src/data/package.json")]);
+        assert_eq!(counters.skipped, 1);
+        assert!(test_logger::contains(
+            log::Level::Warn,
+            "Skipping ./src/fixtures/removed/package.json: listed in the manifest but not found"
+        ));
+        fs::remove_file(path).unwrap();
     }
 
     #[test]
@@ -83,13 +538,23 @@ mod tests {
 
     #[test]
     fn it_should_ignore_folders_from_ignore_file() {
-        let files = get_package_json_files("./src/data/", &["/src/data".to_string()]);
+        let files = get_package_json_files(
+            "./src/data/",
+            &["/src/data".to_string()],
+            None,
+            &mut Counters::default(),
+        );
         assert_eq!(files.len(), 0);
     }
 
     #[test]
     fn it_should_not_ignore_if_no_slash() {
-        let files = get_package_json_files("./src/data/", &["src".to_string()]);
+        let files = get_package_json_files(
+            "./src/data/",
+            &["src".to_string()],
+            None,
+            &mut Counters::default(),
+        );
         assert_eq!(files.len(), 1);
     }
 
@@ -99,12 +564,170 @@ mod tests {
         assert!(!is_node_modules_path(path));
     }
 
+    #[test]
+    fn it_should_find_repo_root_by_git_folder() {
+        let root = env::temp_dir().join("nodedup-repo-root-test");
+        let app = root.join("apps/web");
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(&app).unwrap();
+
+        assert_eq!(find_repo_root(&app), Some(root.as_path()));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn it_should_find_repo_root_by_workspaces() {
+        let app = Path::new("./src/fixtures/repo_root/packages/a")
+            .canonicalize()
+            .unwrap();
+
+        let root = find_repo_root(&app).unwrap();
+
+        assert!(root.ends_with("fixtures/repo_root"));
+    }
+
+    #[test]
+    fn it_should_resolve_repo_root_relative_to_call_directory() {
+        let root = resolve_repo_root("./src/fixtures/repo_root/packages/a");
+
+        assert_eq!(root, "src/fixtures/repo_root");
+    }
+
+    #[test]
+    fn it_should_scan_only_declared_workspaces() {
+        let files =
+            get_workspace_package_json_files("./src/fixtures/workspaces", &mut Counters::default());
+
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("./src/fixtures/workspaces/package.json"),
+                PathBuf::from("./src/fixtures/workspaces/packages/a/package.json"),
+                PathBuf::from("./src/fixtures/workspaces/packages/b/package.json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_keep_only_files_under_path_prefix() {
+        let root = "./src/fixtures/workspaces";
+        let files = get_package_json_files(root, &[], None, &mut Counters::default());
+
+        let mut files = filter_by_path_prefix(files, root, "./packages");
+        files.sort();
+
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("./src/fixtures/workspaces/packages/a/package.json"),
+                PathBuf::from("./src/fixtures/workspaces/packages/b/package.json"),
+            ]
+        );
+        assert!(filter_by_path_prefix(files, root, "packages/a/package").is_empty());
+    }
+
+    #[test]
+    fn it_should_keep_only_changed_workspaces() {
+        let root = "./src/fixtures/workspaces";
+        let files = get_package_json_files(root, &[], None, &mut Counters::default());
+        let changed = vec![
+            Path::new(root).join("packages/a/src/index.js"),
+            Path::new(root).join("tools/c/package.json"),
+        ];
+
+        let mut files = filter_changed_workspaces(files, &changed);
+        files.sort();
+
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("./src/fixtures/workspaces/packages/a/package.json"),
+                PathBuf::from("./src/fixtures/workspaces/tools/c/package.json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_fall_back_when_git_diff_fails() {
+        let changed = get_changed_files("./src/fixtures/workspaces", "no-such-ref-for-nodedup");
+
+        assert_eq!(changed, None);
+    }
+
+    #[test]
+    fn it_should_read_pnpm_workspaces() {
+        let files = get_workspace_package_json_files(
+            "./src/fixtures/pnpm_workspaces",
+            &mut Counters::default(),
+        );
+
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("./src/fixtures/pnpm_workspaces/package.json"),
+                PathBuf::from("./src/fixtures/pnpm_workspaces/apps/web/package.json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_read_workspace_patterns() {
+        let patterns = read_workspace_patterns(Path::new("./src/fixtures/workspaces"));
+
+        assert_eq!(patterns, Some(vec!["packages/*".to_string()]));
+        assert_eq!(read_workspace_patterns(Path::new("./src/data")), None);
+    }
+
+    #[should_panic]
+    #[test]
+    fn it_should_panic_without_workspaces() {
+        get_workspace_package_json_files("./src/data", &mut Counters::default());
+    }
+
     #[test]
     fn it_should_find_ignore_file() {
         let file = get_ignore_file("./src/data/");
         assert!(file.is_some(), "Expected Some, got {:?}", file);
     }
 
+    #[test]
+    fn it_should_find_ignore_file_in_parent_folder() {
+        let file = get_ignore_file("./src/fixtures/ndignore_parent/app");
+        assert!(
+            file.as_deref()
+                .is_some_and(|f| f.ends_with("ndignore_parent/.ndignore")),
+            "Expected parent .ndignore, got {:?}",
+            file
+        );
+    }
+
+    #[test]
+    fn it_should_find_npmrc_file() {
+        let path = get_npmrc_file("./src/fixtures/npmrc").unwrap();
+
+        assert!(path.ends_with("src/fixtures/npmrc/.npmrc"));
+        assert_eq!(get_npmrc_file("./src/fixtures/stats"), None);
+    }
+
+    #[test]
+    fn it_should_prefer_custom_ignore_file() {
+        let path = "./src/fixtures/custom_ignore/config/nodedup.ignore";
+        let file = resolve_ignore_file("./src/data/", Some(path));
+        assert_eq!(file.as_deref(), Some(path));
+    }
+
+    #[test]
+    fn it_should_discover_ignore_file_without_custom_path() {
+        let file = resolve_ignore_file("./src/data/", None);
+        assert!(file.is_some_and(|f| f.ends_with("data/.ndignore")));
+    }
+
+    #[should_panic]
+    #[test]
+    fn it_should_panic_for_missing_custom_ignore_file() {
+        resolve_ignore_file("./src/data/", Some("./src/data/missing.ignore"));
+    }
+
     #[test]
     fn it_should_return_empty_string_if_no_file() {
         let file = get_ignore_file("./src");
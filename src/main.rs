@@ -1,49 +1,1383 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    io::{self, IsTerminal, Write},
+    path::Path,
+    time::Instant,
+};
+
 use clap::Parser;
+use log::LevelFilter;
+use regex::{Captures, Regex};
 
-use crate::formatter::DependenciesFormatter;
-use crate::parser::get_ignore_values;
+use crate::baseline::Baseline;
+use crate::diagnostics::{Counters, Diagnostics, Summary};
+use crate::exit::{FailOn, Failures};
+use crate::formatter::{
+    format_bundled_mismatches, format_case_collisions, format_case_conflicts,
+    format_duplicate_keys, format_edits, format_missing_dependencies,
+    format_non_registry_dependencies, format_phantom_dependencies, format_policy_violations,
+    format_resolution_drift, format_resolved_duplicates, format_types_mismatches,
+    format_version_changes, format_workspace_protocol_mixes, ColorScheme, DependenciesFormatter,
+};
+use crate::parser::{
+    get_ignore_entries, get_ignore_values, get_scope_ignores, merge_ignores, Granularity,
+    IgnoreEntry, PackageValue,
+};
 
+mod baseline;
+mod compare;
+mod compat;
+mod deno;
+mod diagnostics;
+mod exit;
+mod fix;
 mod formatter;
+mod lockfile;
 mod lookup;
+mod npmrc;
 mod parser;
+mod phantom;
+mod policy;
+#[cfg(feature = "registry-check")]
+mod registry;
+mod tarball;
+#[cfg(test)]
+mod test_logger;
+mod types;
+mod watch;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Folder to scan
-    #[arg(short, long)]
+    /// Folder or package.json file to scan
+    #[arg(default_value = ".")]
     folder: String,
 
-    /// Output format. Possible values: 'default', 'short', 'full'
+    /// Same as the positional folder argument
+    #[arg(
+        short = 'f',
+        long = "folder",
+        value_name = "FOLDER",
+        conflicts_with = "folder"
+    )]
+    folder_option: Option<String>,
+
+    /// Scan from the nearest parent folder containing .git or a package.json with workspaces
+    #[arg(long)]
+    repo_root: bool,
+
+    /// Scan only the workspaces declared in the root package.json or pnpm-workspace.yaml
+    #[arg(long)]
+    workspaces: bool,
+
+    /// Skip package.json files in folders N or more levels below the scanned folder
+    #[arg(long, value_name = "N")]
+    ignore_below_depth: Option<usize>,
+
+    /// Keep only package.json files under this path, relative to the scanned folder
+    #[arg(long, value_name = "PATH")]
+    path_prefix: Option<String>,
+
+    /// Keep only workspaces with files changed since the git ref, scan all if git fails
+    #[arg(long, value_name = "REF")]
+    since: Option<String>,
+
+    /// Read package.json paths from a manifest file instead of scanning the folder
+    #[arg(long, value_name = "PATH")]
+    manifest: Option<String>,
+
+    /// Scan the package.json files inside an npm pack tarball instead of the folder
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["manifest", "workspaces", "repo_root", "case_insensitive", "split_by_section"])]
+    from_tarball: Option<String>,
+
+    /// Fail when a folder could not be read while scanning
+    #[arg(long)]
+    strict: bool,
+
+    /// Print the package.json paths that would be scanned, one per line, and exit
+    #[arg(long)]
+    list_files: bool,
+
+    /// Save the package.json paths found to a manifest file
+    #[arg(long, value_name = "PATH")]
+    write_manifest: Option<String>,
+
+    /// Dependency name or glob to ignore, added to the ignore file entries
+    #[arg(long, value_name = "NAME")]
+    ignore: Vec<String>,
+
+    /// Use only the --ignore entries and skip the ignore file
+    #[arg(long)]
+    ignore_only: bool,
+
+    /// JSON pointer to an extra object read as a dependency map, e.g. /acme:deps
+    #[arg(long, value_name = "POINTER")]
+    field: Vec<String>,
+
+    /// Ignore file to use instead of the nearest .ndignore
+    #[arg(long, value_name = "PATH")]
+    ignore_file: Option<String>,
+
+    /// Output format. Possible values: 'default', 'short', 'full', 'toml', 'json', 'table', 'line', 'ndjson', 'yaml', 'dot', 'junit', 'none'
     #[arg(short, long, default_value = "default")]
     output: String,
 
+    /// Write the report to a file instead of stdout, color is only kept with --color
+    #[arg(long, value_name = "PATH")]
+    out: Option<String>,
+
+    /// Indent JSON output
+    #[arg(long)]
+    json_pretty: bool,
+
+    /// Leave dependencies of the given npm scope out of the report
+    #[arg(long, value_name = "SCOPE")]
+    exclude_scope: Vec<String>,
+
+    /// Report only the N dependencies with the most distinct versions
+    #[arg(long, value_name = "N")]
+    top: Option<usize>,
+
+    /// Count the files declaring each major version of a duplicated dependency
+    #[arg(long)]
+    by_major: bool,
+
+    /// Show the registry each scoped duplicate resolves to, read from the nearest .npmrc
+    #[arg(long)]
+    show_registry: bool,
+
+    /// Summarize package folders sharing a parent and a version as `parent/* (N)`
+    #[arg(long)]
+    dedupe_paths: bool,
+
+    /// After the report, list the highest version of each duplicated package as `name -> version`
+    #[arg(long)]
+    recommend: bool,
+
+    /// List a group's locations on one line, separated by commas
+    #[arg(long)]
+    compact_locations: bool,
+
+    /// List every dependency and version found, not only duplicated ones
+    #[arg(long)]
+    all: bool,
+
+    /// Stop the printed report after N lines, json, ndjson, yaml, toml, dot and junit output is never cut
+    #[arg(long, value_name = "N")]
+    max_lines: Option<usize>,
+
+    /// Print only the number of duplicated dependencies
+    #[arg(long)]
+    count_only: bool,
+
+    /// What to count towards the exit code
+    #[arg(long, value_enum, default_value_t)]
+    fail_on: FailOn,
+
+    /// Stop reading package.json files at the first duplicate and exit with code 1
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "from_tarball",
+            "case_insensitive",
+            "split_by_section",
+            "fail_on",
+            "silent_threshold",
+            "fail_threshold_percent",
+        ]
+    )]
+    fail_fast: bool,
+
     /// Exit with zero code when duplicates are found
     #[arg(short, long)]
     silent: bool,
 
+    /// Exit with non-zero code only when more than N duplicates are found
+    #[arg(long, value_name = "N")]
+    silent_threshold: Option<usize>,
+
+    /// Exit with non-zero code only when more than N percent of the unique dependencies are duplicated
+    #[arg(long, value_name = "N")]
+    fail_threshold_percent: Option<f64>,
+
+    /// Do not count duplicates found only in devDependencies towards the exit code
+    #[arg(long)]
+    dev_as_warning: bool,
+
     /// Color important output
     #[arg(short, long)]
     color: bool,
+
+    /// Colors used with --color, 'none' turns coloring off
+    #[arg(long, value_enum, default_value_t)]
+    color_scheme: ColorScheme,
+
+    /// Group duplicates by npm scope and print per-scope counts
+    #[arg(long)]
+    group_scope: bool,
+
+    /// Print aggregate numbers instead of the per-package listing
+    #[arg(long)]
+    stats: bool,
+
+    /// Print locations relative to the scanned folder
+    #[arg(long)]
+    relative_paths: bool,
+
+    /// Report dependencies declared twice in the same object
+    #[arg(long)]
+    strict_parse: bool,
+
+    /// Compare versions resolved for each workspace in a package-lock.json
+    #[arg(long, value_name = "PATH")]
+    lockfile: Option<String>,
+
+    /// Save the found duplicates as a baseline file
+    #[arg(long, value_name = "PATH")]
+    write_baseline: Option<String>,
+
+    /// List the ignored packages with the reason noted next to them in the ignore file
+    #[arg(long)]
+    show_ignored: bool,
+
+    /// Report dependencies declared with another version than the one approved in a JSON policy file
+    #[arg(long, value_name = "PATH")]
+    policy: Option<String>,
+
+    /// Report dependencies whose declared range does not match the version installed in a package-lock.json
+    #[arg(long, value_name = "PATH")]
+    lock_policy: Option<String>,
+
+    /// Report only duplicates added or resolved since the baseline file
+    #[arg(long, value_name = "PATH", group = "baseline_source")]
+    baseline: Option<String>,
+
+    /// With a baseline, list every duplicate but fail only on those missing from the baseline
+    #[arg(long, requires = "baseline_source")]
+    baseline_ignore_new: bool,
+
+    /// Report only duplicates added or resolved since the baseline served at the URL
+    #[cfg(feature = "remote-baseline")]
+    #[arg(
+        long,
+        value_name = "URL",
+        conflicts_with = "baseline",
+        group = "baseline_source"
+    )]
+    baseline_url: Option<String>,
+
+    /// Report duplicated versions that are not published on the registry from the nearest .npmrc
+    #[cfg(feature = "registry-check")]
+    #[arg(long)]
+    registry_check: bool,
+
+    /// Print how the versions of each dependency changed between two git refs and exit
+    #[arg(long, value_name = "REF1..REF2")]
+    compare: Option<String>,
+
+    /// List package.json files that do not declare the given dependency
+    #[arg(long, value_name = "NAME")]
+    report_missing: Vec<String>,
+
+    /// Do not report versions whose ranges can be satisfied by a single release
+    #[arg(long)]
+    semver_compat: bool,
+
+    /// Leave prerelease versions such as 1.0.0-next.5 out of duplicate checks
+    #[arg(long)]
+    ignore_prerelease: bool,
+
+    /// List packages declared with the same version in several package.json files, without failing on them
+    #[arg(long)]
+    report_same_version: bool,
+
+    /// Version part up to which versions must differ to count as duplicates
+    #[arg(long, value_enum, default_value_t)]
+    granularity: Granularity,
+
+    /// Report only duplicates declared in at least this many package.json files
+    #[arg(long, value_name = "K")]
+    min_files: Option<usize>,
+
+    /// Treat dependency names differing only in letter case as the same package
+    #[arg(long)]
+    case_insensitive: bool,
+
+    /// Compare versions only within the same dependency section
+    #[arg(long, conflicts_with = "case_insensitive")]
+    split_by_section: bool,
+
+    /// List every occurrence, including a version repeated in several sections of one file (for debugging)
+    #[arg(long, conflicts_with_all = ["from_tarball", "case_insensitive", "split_by_section"])]
+    no_dedupe_same_version: bool,
+
+    /// Report package.json files declaring the same dependency with names differing only in case
+    #[arg(long)]
+    check_file_casing: bool,
+
+    /// Report @types packages whose major version differs from the package they describe
+    #[arg(long)]
+    check_types_alignment: bool,
+
+    /// Report dependencies installed from git, URLs or local paths instead of the registry
+    #[arg(long)]
+    check_non_registry: bool,
+
+    /// Report local workspaces referenced with workspace: in some files and a registry range in others
+    #[arg(long)]
+    check_workspace_protocol: bool,
+
+    /// Report packages imported by a workspace's sources but only declared by other workspaces
+    #[arg(long)]
+    check_phantom: bool,
+
+    /// Report bundled dependencies declared with other versions elsewhere
+    #[arg(long)]
+    check_bundled: bool,
+
+    /// Rewrite package.json files to use the highest version of each duplicated dependency
+    #[arg(long)]
+    fix: bool,
+
+    /// Print the changes --fix would make without writing them, exit with code 1 if there are any
+    #[arg(long, requires = "fix")]
+    dry_run: bool,
+
+    /// Ask which version of each duplicated dependency to keep, then rewrite package.json files
+    #[arg(long)]
+    interactive: bool,
+
+    /// Print what each exit code means and exit
+    #[arg(long)]
+    explain_exit: bool,
+
+    /// Keep running and report again whenever a package.json or deno.json changes
+    #[arg(short, long)]
+    watch: bool,
+
+    /// Print progress, timings and file counts to stderr
+    #[arg(short, long, conflicts_with = "quiet")]
+    verbose: bool,
+
+    /// Print only errors to stderr
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Do not print the one-line summary to stderr
+    #[arg(long)]
+    no_summary: bool,
 }
 
+impl Args {
+    fn folder(&self) -> &str {
+        self.folder_option.as_deref().unwrap_or(&self.folder)
+    }
+}
+
+// Shells differ in what they expand, so `~`, `$VAR` and `${VAR}` are expanded here as well
+fn expand_folder(folder: &str) -> Result<String, String> {
+    let folder = match folder.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("${{HOME}}{}", rest),
+        _ => folder.to_string(),
+    };
+    let mut undefined: Option<String> = None;
+    let expanded = Regex::new(r"\$\{(\w+)\}|\$(\w+)")
+        .unwrap()
+        .replace_all(&folder, |caps: &Captures| {
+            let name = caps.get(1).or(caps.get(2)).unwrap().as_str();
+            env::var(name).unwrap_or_else(|_| {
+                undefined.get_or_insert(name.to_string());
+                String::new()
+            })
+        })
+        .into_owned();
+
+    match undefined {
+        Some(name) => Err(format!(
+            "Failed to expand the folder: {} is not defined",
+            name
+        )),
+        None => Ok(expanded),
+    }
+}
+
+// Both streams stay locked for the whole run, so nothing else can write between its lines
 fn main() {
-    let args = Args::parse();
+    let mut args = Args::parse();
+    init_logger(&args);
+    exit::exit_on_panic();
+    let mut stdout = io::stdout().lock();
+    let mut stderr = io::stderr().lock();
+    match expand_folder(args.folder()) {
+        Ok(folder) => {
+            args.folder = folder;
+            args.folder_option = None;
+        }
+        Err(error) => {
+            writeln!(stderr, "{}", error).unwrap();
+            std::process::exit(exit::USAGE_ERROR);
+        }
+    }
+
+    if args.explain_exit {
+        write!(stdout, "{}", exit::explain_exit_codes()).unwrap();
+        return;
+    }
+
+    if args.watch {
+        let mut report = || {
+            write!(stdout, "{}", watch::CLEAR_SCREEN).unwrap();
+            run(&args, &mut stdout, &mut stderr);
+        };
+        report();
+        watch::watch(args.folder(), report);
+        return;
+    }
+
+    std::process::exit(run(&args, &mut stdout, &mut stderr));
+}
+
+// RUST_LOG takes precedence over the level picked by the flags
+fn init_logger(args: &Args) {
+    let level = if args.quiet {
+        LevelFilter::Error
+    } else if args.verbose {
+        LevelFilter::Info
+    } else {
+        LevelFilter::Warn
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .format_timestamp(None)
+        .format_target(false)
+        .parse_default_env()
+        .init();
+}
+
+fn retain_reported(duplicates: &mut HashMap<String, Vec<PackageValue>>, args: &Args) {
+    if args.ignore_prerelease {
+        parser::drop_prereleases(duplicates);
+    }
+    parser::retain_by_granularity(duplicates, args.granularity);
+    if let Some(min_files) = args.min_files {
+        parser::retain_by_min_files(duplicates, min_files);
+    }
+    if args.semver_compat {
+        compat::retain_conflicting(duplicates);
+    }
+}
+
+fn read_previous_baseline(args: &Args) -> Option<Baseline> {
+    let previous = args.baseline.as_deref().map(baseline::read_baseline);
+    #[cfg(feature = "remote-baseline")]
+    let previous = previous.or_else(|| args.baseline_url.as_deref().map(baseline::fetch_baseline));
+
+    previous
+}
 
-    let folder = args.folder;
-    let ignore = lookup::get_ignore_file(&folder);
+fn run(args: &Args, stdout: &mut impl Write, stderr: &mut impl Write) -> i32 {
+    let run_started = Instant::now();
+    let folder = if args.repo_root {
+        &lookup::resolve_repo_root(args.folder())
+    } else {
+        args.folder()
+    };
+    let ignore = lookup::resolve_ignore_file(folder, args.ignore_file.as_deref());
     let ignore = ignore.unwrap_or_default();
-    let ignores = get_ignore_values(&ignore);
-    let files = lookup::get_package_json_files(&folder, &ignores);
-    let duplicates = parser::find_duplicate_dependencies(files, &ignores);
-    let errors = duplicates.len() as i32;
-    let mut formatter = DependenciesFormatter::new(duplicates);
-    formatter.try_set_style(&args.output);
-    let result = formatter.format(args.color);
-    println!("{}", result);
-
-    if args.silent {
-        std::process::exit(0);
-    }
-    std::process::exit(errors);
+    let ignores = merge_ignores(get_ignore_values(&ignore), &args.ignore, args.ignore_only);
+    let ignored = if args.show_ignored {
+        let file_entries = if args.ignore_only {
+            vec![]
+        } else {
+            get_ignore_entries(&ignore)
+        };
+        let cli_entries = args.ignore.iter().map(|pattern| IgnoreEntry {
+            pattern: pattern.clone(),
+            reason: None,
+        });
+        file_entries.into_iter().chain(cli_entries).collect()
+    } else {
+        vec![]
+    };
+    if let Some(field) = args.field.iter().find(|field| !field.starts_with('/')) {
+        writeln!(
+            stderr,
+            "Failed to read the field: {}, JSON pointers start with /",
+            field
+        )
+        .unwrap();
+        std::process::exit(exit::USAGE_ERROR);
+    }
+    if let Some(range) = &args.compare {
+        let (from, to) = compare::parse_range(range).unwrap_or_else(|error| {
+            writeln!(stderr, "{}", error).unwrap();
+            std::process::exit(exit::USAGE_ERROR);
+        });
+        let changes = compare::compare_contents(
+            &compare::read_contents_at(folder, from),
+            &compare::read_contents_at(folder, to),
+            &ignores,
+            &args.field,
+        );
+        let colors = if args.color {
+            args.color_scheme
+        } else {
+            ColorScheme::None
+        };
+        write!(
+            stdout,
+            "{}",
+            format_version_changes(&changes, from, to, colors)
+        )
+        .unwrap();
+        return exit::SUCCESS;
+    }
+    let mut diagnostics = Diagnostics::default();
+    let started = Instant::now();
+    let files = match &args.manifest {
+        Some(manifest) => lookup::read_manifest(manifest, &mut diagnostics.counters),
+        // The tarball entries are read in memory further down, there is nothing to walk
+        None if args.from_tarball.is_some() => vec![],
+        None if args.workspaces => {
+            lookup::get_workspace_package_json_files(folder, &mut diagnostics.counters)
+        }
+        None => lookup::get_package_json_files(
+            folder,
+            &ignores,
+            args.ignore_below_depth,
+            &mut diagnostics.counters,
+        ),
+    };
+    let files = match &args.path_prefix {
+        Some(prefix) => lookup::filter_by_path_prefix(files, folder, prefix),
+        None => files,
+    };
+    let changed = args
+        .since
+        .as_ref()
+        .and_then(|since| lookup::get_changed_files(folder, since));
+    let files = match &changed {
+        Some(changed) => lookup::filter_changed_workspaces(files, changed),
+        None => files,
+    };
+    if let Some(manifest) = &args.write_manifest {
+        lookup::write_manifest(manifest, &files);
+    }
+    diagnostics.walk = started.elapsed();
+    if args.list_files {
+        write!(stdout, "{}", lookup::format_file_list(&files)).unwrap();
+        return exit::SUCCESS;
+    }
+    if args.strict && diagnostics.counters.unreadable > 0 {
+        log::error!(
+            "Failed to read {} folders, run with --verbose to list them",
+            diagnostics.counters.unreadable
+        );
+        return exit::WALK_ERROR;
+    }
+    if args.fail_fast {
+        let name_ignores = [ignores, get_scope_ignores(&args.exclude_scope)].concat();
+        let previous = read_previous_baseline(args);
+        let duplicate = parser::find_first_duplicate(
+            &files,
+            &name_ignores,
+            &args.field,
+            &mut diagnostics.counters,
+            |name, values| {
+                let mut group = HashMap::from([(name.to_string(), values.to_vec())]);
+                retain_reported(&mut group, args);
+                group
+                    .values()
+                    .any(|values| !(args.dev_as_warning && parser::is_dev_only(values)))
+                    && previous
+                        .as_ref()
+                        .is_none_or(|previous| !previous.duplicates.contains_key(name))
+            },
+        );
+        return match duplicate {
+            Some((name, path)) => {
+                if args.output != "none" {
+                    writeln!(
+                        stderr,
+                        "{}, Second version found in {}",
+                        name,
+                        path.display()
+                    )
+                    .unwrap();
+                }
+                exit::get_exit_code(1, args.silent, None)
+            }
+            None => exit::SUCCESS,
+        };
+    }
+    let duplicate_keys = if args.strict_parse {
+        parser::find_duplicate_keys(&files)
+    } else {
+        vec![]
+    };
+    let bundled = if args.check_bundled {
+        parser::find_bundled_mismatches(&files)
+    } else {
+        vec![]
+    };
+    let drift = args
+        .lockfile
+        .as_ref()
+        .map(|lockfile| lockfile::find_resolution_drift(lockfile, &ignores))
+        .unwrap_or_default();
+    let files_scanned = files.len();
+    let needs_all_dependencies = args.all
+        || args.stats
+        || args.fail_threshold_percent.is_some()
+        || args.case_insensitive
+        || args.check_file_casing
+        || args.check_types_alignment
+        || args.check_non_registry
+        || args.check_workspace_protocol
+        || args.check_phantom
+        || args.report_same_version
+        || args.policy.is_some()
+        || args.lock_policy.is_some()
+        || !args.report_missing.is_empty();
+    let all_dependencies = if needs_all_dependencies {
+        parser::collect_dependencies(&files, &args.field, &mut Counters::default())
+    } else {
+        HashMap::new()
+    };
+    let total_dependencies = args.stats.then_some(all_dependencies.len());
+    let missing =
+        parser::find_missing_dependencies(&files, &all_dependencies, &args.report_missing);
+    let types_mismatches = if args.check_types_alignment {
+        types::find_types_mismatches(&all_dependencies)
+    } else {
+        vec![]
+    };
+    let non_registry = if args.check_non_registry {
+        parser::find_non_registry_dependencies(&all_dependencies)
+    } else {
+        vec![]
+    };
+    let workspace_protocol_mixes = if args.check_workspace_protocol {
+        let package_names = parser::collect_package_names(&files);
+        parser::find_workspace_protocol_mixes(&all_dependencies, &package_names)
+    } else {
+        BTreeMap::new()
+    };
+    let policy_violations = args
+        .policy
+        .as_deref()
+        .map(|path| policy::find_policy_violations(&all_dependencies, &policy::read_policy(path)))
+        .unwrap_or_default();
+    let lock_violations = args
+        .lock_policy
+        .as_deref()
+        .map(|path| lockfile::find_lock_violations(path, &all_dependencies))
+        .unwrap_or_default();
+    let phantoms = if args.check_phantom {
+        phantom::find_phantom_dependencies(&files, &all_dependencies)
+    } else {
+        vec![]
+    };
+    let case_collisions = if args.check_file_casing {
+        parser::find_case_collisions(&all_dependencies)
+    } else {
+        vec![]
+    };
+    let case_conflicts = if args.case_insensitive {
+        parser::find_case_conflicts(&all_dependencies)
+    } else {
+        BTreeMap::new()
+    };
+    let started = Instant::now();
+    let name_ignores = [ignores, get_scope_ignores(&args.exclude_scope)].concat();
+    let same_version = if args.report_same_version {
+        parser::find_same_version_groups(&all_dependencies, &name_ignores)
+    } else {
+        vec![]
+    };
+    let scan = if let Some(tarball) = &args.from_tarball {
+        let contents = tarball::read_package_json_entries(tarball);
+        parser::find_duplicates_in_contents(
+            &contents,
+            &name_ignores,
+            &args.field,
+            &mut diagnostics.counters,
+        )
+    } else if args.case_insensitive {
+        parser::find_case_insensitive_duplicates(
+            files,
+            &name_ignores,
+            &args.field,
+            &mut diagnostics.counters,
+        )
+    } else if args.split_by_section {
+        parser::find_duplicates_by_section(
+            files,
+            &name_ignores,
+            &args.field,
+            &mut diagnostics.counters,
+        )
+    } else if args.no_dedupe_same_version {
+        parser::find_every_occurrence(files, &name_ignores, &args.field, &mut diagnostics.counters)
+    } else {
+        parser::find_duplicate_dependencies(
+            files,
+            &name_ignores,
+            &args.field,
+            &mut diagnostics.counters,
+        )
+    };
+    diagnostics.parse = started.elapsed();
+    let mut duplicates = scan.duplicates;
+    retain_reported(&mut duplicates, args);
+    #[cfg(feature = "registry-check")]
+    let unpublished = if args.registry_check {
+        let registries = lookup::get_npmrc_file(folder)
+            .map(|path| npmrc::read_npmrc(&path))
+            .unwrap_or_default();
+        let mut cache = registry::RegistryCache::default();
+        registry::find_unpublished_versions(&duplicates, &registries, &mut cache)
+    } else {
+        vec![]
+    };
+    let mut failures = Failures {
+        duplicates: exit::count_failing(&duplicates, args.dev_as_warning),
+        invalid: scan.errors + duplicate_keys.len(),
+        missing: missing.values().map(Vec::len).sum(),
+    };
+    let current = Baseline::new(&duplicates);
+    if let Some(path) = &args.write_baseline {
+        baseline::write_baseline(path, &current);
+    }
+    let previous = read_previous_baseline(args);
+    let mut new_groups = None;
+    let resolved = match previous {
+        Some(previous) => {
+            let diff = previous.diff(&current);
+            if args.baseline_ignore_new {
+                let added = diff.added.into_iter().collect();
+                failures.duplicates =
+                    exit::count_new_failing(&duplicates, &added, args.dev_as_warning);
+                new_groups = Some(added);
+            } else {
+                duplicates.retain(|name, _| diff.added.contains(name));
+                failures.duplicates = exit::count_failing(&duplicates, args.dev_as_warning);
+            }
+            diff.resolved
+        }
+        None => vec![],
+    };
+    let interactive = args.interactive && io::stdin().is_terminal();
+    if args.interactive && !interactive {
+        log::warn!("Skipping --interactive: stdin is not a terminal");
+    }
+    let chosen = if interactive {
+        fix::prompt_choices(&duplicates, &mut io::stdin().lock(), stdout)
+    } else if args.fix {
+        fix::choose_highest(&duplicates)
+    } else {
+        BTreeMap::new()
+    };
+    let edits = fix::compute_edits(&duplicates, &chosen);
+    let edits = if args.dry_run {
+        edits
+    } else {
+        fix::apply_edits(edits)
+    };
+    if !args.dry_run && !edits.is_empty() {
+        // The report and exit code describe the files as they are after the fix
+        duplicates.retain(|name, values| {
+            !chosen
+                .get(name)
+                .is_some_and(|to| fix::is_fixed(values, to, &edits))
+        });
+        failures.duplicates = match &new_groups {
+            Some(added) => exit::count_new_failing(&duplicates, added, args.dev_as_warning),
+            None => exit::count_failing(&duplicates, args.dev_as_warning),
+        };
+    }
+    let duplicates_found = duplicates.len();
+    let recommendations = if args.recommend {
+        parser::get_recommendations(&duplicates)
+    } else {
+        BTreeMap::new()
+    };
+    // The inventory is only displayed, the exit code above is still based on duplicates
+    let mut formatter = if args.all {
+        DependenciesFormatter::new(parser::get_inventory(&all_dependencies, &name_ignores))
+    } else {
+        DependenciesFormatter::new(duplicates)
+    };
+    if let Err(error) = formatter.try_set_style(&args.output) {
+        writeln!(stderr, "{}", error).unwrap();
+        std::process::exit(exit::USAGE_ERROR);
+    }
+    if let Some(total_dependencies) = total_dependencies {
+        formatter.set_stats(files_scanned, total_dependencies);
+    }
+    formatter.set_group_scope(args.group_scope);
+    formatter.set_json_pretty(args.json_pretty);
+    formatter.set_duplicate_count(duplicates_found);
+    formatter.set_top(args.top);
+    formatter.set_count_only(args.count_only);
+    formatter.set_dedupe_paths(args.dedupe_paths);
+    formatter.set_compact_locations(args.compact_locations);
+    formatter.set_by_major(args.by_major);
+    formatter.set_new_groups(new_groups.filter(|_| !args.all));
+    // The notice points at --out, so a report written to a file is kept whole
+    formatter.set_max_lines(args.max_lines.filter(|_| args.out.is_none()));
+    if args.show_registry {
+        let npmrc = lookup::get_npmrc_file(folder);
+        formatter.set_registries(Some(
+            npmrc
+                .map(|path| npmrc::read_npmrc(&path))
+                .unwrap_or_default(),
+        ));
+    }
+    // A single scanned manifest is shown relative to its own folder
+    let relative_root = match Path::new(folder).parent() {
+        Some(parent) if Path::new(folder).is_file() => parent.to_string_lossy().to_string(),
+        _ => folder.to_string(),
+    };
+    formatter.set_relative_root(args.relative_paths.then_some(relative_root));
+    let colors = if args.color {
+        args.color_scheme
+    } else {
+        ColorScheme::None
+    };
+    let started = Instant::now();
+    let result = formatter.format(colors);
+    diagnostics.format = started.elapsed();
+    let mut sections = vec![result];
+    let mut extra: Vec<String> = Vec::new();
+    // Extra sections would break scripts capturing the count
+    if !args.count_only {
+        if !duplicate_keys.is_empty() {
+            extra.push(format_duplicate_keys(&duplicate_keys, colors));
+        }
+        if !args.report_missing.is_empty() {
+            extra.push(format_missing_dependencies(&missing, colors));
+        }
+        if !case_conflicts.is_empty() {
+            extra.push(format_case_conflicts(&case_conflicts, colors));
+        }
+        if !case_collisions.is_empty() {
+            extra.push(format_case_collisions(&case_collisions, colors));
+        }
+        if !types_mismatches.is_empty() {
+            extra.push(format_types_mismatches(&types_mismatches, colors));
+        }
+        if !non_registry.is_empty() {
+            extra.push(format_non_registry_dependencies(&non_registry, colors));
+        }
+        if !same_version.is_empty() {
+            extra.push(formatter::format_same_version_groups(&same_version, colors));
+        }
+        if !workspace_protocol_mixes.is_empty() {
+            extra.push(format_workspace_protocol_mixes(
+                &workspace_protocol_mixes,
+                colors,
+            ));
+        }
+        if !policy_violations.is_empty() {
+            extra.push(format_policy_violations(&policy_violations, colors));
+        }
+        if !lock_violations.is_empty() {
+            extra.push(formatter::format_lock_violations(&lock_violations, colors));
+        }
+        if !phantoms.is_empty() {
+            extra.push(format_phantom_dependencies(&phantoms, colors));
+        }
+        if !bundled.is_empty() {
+            extra.push(format_bundled_mismatches(&bundled, colors));
+        }
+        if !drift.is_empty() {
+            extra.push(format_resolution_drift(&drift, colors));
+        }
+        #[cfg(feature = "registry-check")]
+        if !unpublished.is_empty() {
+            extra.push(formatter::format_unpublished_versions(&unpublished, colors));
+        }
+        if !resolved.is_empty() {
+            extra.push(format_resolved_duplicates(&resolved, colors));
+        }
+        if !edits.is_empty() {
+            extra.push(format_edits(&edits, args.dry_run, colors));
+        }
+        if !recommendations.is_empty() {
+            extra.push(formatter::format_recommendations(&recommendations, colors));
+        }
+        if !ignored.is_empty() {
+            extra.push(formatter::format_ignored(&ignored, colors));
+        }
+    }
+    // Plain text after a JSON document or similar would leave output that cannot be parsed
+    if formatter.is_machine_readable() && !extra.is_empty() {
+        log::warn!(
+            "Skipping the extra report sections: --output {} only holds the duplicates",
+            args.output
+        );
+    } else {
+        sections.extend(extra);
+    }
+    // With --output none the run is a pure gate, only the exit code tells the result
+    let no_output = args.output == "none";
+    if !no_output {
+        // An empty ndjson report has no lines at all, not a blank one
+        let output: String = sections
+            .iter()
+            .filter(|s| !s.is_empty())
+            .map(|s| format!("{}\n", s))
+            .collect();
+        match &args.out {
+            Some(path) => formatter::write_report(path, &output),
+            None => write!(stdout, "{}", output).unwrap(),
+        }
+    }
+    if args.verbose {
+        writeln!(stderr, "{}", diagnostics).unwrap();
+    }
+    // Written to stderr so machine formats on stdout stay parseable
+    if !args.no_summary && !args.quiet && !no_output {
+        let summary = Summary {
+            duplicates: duplicates_found,
+            files: scan.files_scanned,
+            elapsed: run_started.elapsed(),
+        };
+        writeln!(stderr, "{}", summary).unwrap();
+    }
+    if args.dry_run {
+        return exit::get_exit_code(edits.len(), false, None);
+    }
+
+    let unique_dependencies = all_dependencies
+        .keys()
+        .filter(|name| !parser::is_ignored(name, &name_ignores))
+        .count();
+    let failing = match args.fail_threshold_percent {
+        Some(percent) if !exit::exceeds_percent(duplicates_found, unique_dependencies, percent) => {
+            0
+        }
+        _ => failures.count(args.fail_on),
+    };
+
+    exit::get_exit_code(failing, args.silent, args.silent_threshold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_accept_positional_and_named_folder() {
+        let positional = Args::try_parse_from(["nodedup", "./repo"]).unwrap();
+        let named = Args::try_parse_from(["nodedup", "--folder", "./repo"]).unwrap();
+        let short = Args::try_parse_from(["nodedup", "-f", "./repo"]).unwrap();
+
+        assert_eq!(positional.folder(), "./repo");
+        assert_eq!(named.folder(), "./repo");
+        assert_eq!(short.folder(), "./repo");
+    }
+
+    #[test]
+    fn it_should_default_to_current_folder() {
+        let args = Args::try_parse_from(["nodedup"]).unwrap();
+
+        assert_eq!(args.folder(), ".");
+    }
+
+    #[test]
+    fn it_should_expand_home_folder() {
+        let home = env::var("HOME").unwrap();
+
+        assert_eq!(expand_folder("~").unwrap(), home);
+        assert_eq!(expand_folder("~/repo").unwrap(), format!("{}/repo", home));
+        assert_eq!(expand_folder("./~repo").unwrap(), "./~repo");
+    }
+
+    #[test]
+    fn it_should_expand_environment_variables() {
+        env::set_var("NODEDUP_TEST_WORKSPACE", "/ci/checkout");
+
+        assert_eq!(
+            expand_folder("$NODEDUP_TEST_WORKSPACE/packages").unwrap(),
+            "/ci/checkout/packages"
+        );
+        assert_eq!(
+            expand_folder("${NODEDUP_TEST_WORKSPACE}/packages").unwrap(),
+            "/ci/checkout/packages"
+        );
+    }
+
+    #[test]
+    fn it_should_fail_for_undefined_environment_variables() {
+        assert_eq!(
+            expand_folder("$NODEDUP_TEST_UNDEFINED/packages"),
+            Err("Failed to expand the folder: NODEDUP_TEST_UNDEFINED is not defined".to_string())
+        );
+    }
+
+    #[test]
+    fn it_should_keep_exit_code_without_output() {
+        let args = Args::try_parse_from(["nodedup", "./src/fixtures/sections", "--output", "none"])
+            .unwrap();
+        let mut stdout: Vec<u8> = Vec::new();
+        let mut stderr: Vec<u8> = Vec::new();
+
+        assert_eq!(run(&args, &mut stdout, &mut stderr), 1);
+        assert!(stdout.is_empty());
+        assert!(stderr.is_empty());
+    }
+
+    #[test]
+    fn it_should_not_collide_with_error_codes() {
+        let args = |extra: &[&str]| {
+            let mut argv = vec!["nodedup", "./src/fixtures/stats", "--output", "none"];
+            argv.extend_from_slice(extra);
+            Args::try_parse_from(argv).unwrap()
+        };
+
+        assert_eq!(
+            run(&args(&[]), &mut io::sink(), &mut io::sink()),
+            exit::FAILURE
+        );
+        assert_eq!(
+            run(
+                &args(&["--fix", "--dry-run"]),
+                &mut io::sink(),
+                &mut io::sink()
+            ),
+            exit::FAILURE
+        );
+    }
+
+    #[test]
+    fn it_should_fail_above_duplicated_percent() {
+        // lodash and react are duplicated out of lodash, react and express
+        let args = |percent: &str| {
+            Args::try_parse_from([
+                "nodedup",
+                "./src/fixtures/stats",
+                "--output",
+                "none",
+                "--fail-threshold-percent",
+                percent,
+            ])
+            .unwrap()
+        };
+
+        assert_eq!(
+            run(&args("66"), &mut io::sink(), &mut io::sink()),
+            exit::FAILURE
+        );
+        assert_eq!(run(&args("67"), &mut io::sink(), &mut io::sink()), 0);
+    }
+
+    #[test]
+    fn it_should_filter_duplicates_before_failing_fast() {
+        let args = |extra: &[&str]| {
+            let mut argv = vec![
+                "nodedup",
+                "./src/fixtures/stats",
+                "--output",
+                "none",
+                "--fail-fast",
+            ];
+            argv.extend_from_slice(extra);
+            Args::try_parse_from(argv).unwrap()
+        };
+
+        assert_eq!(
+            run(&args(&[]), &mut io::sink(), &mut io::sink()),
+            exit::FAILURE
+        );
+        // Only react is declared in all three files
+        assert_eq!(
+            run(
+                &args(&["--min-files", "3"]),
+                &mut io::sink(),
+                &mut io::sink()
+            ),
+            exit::FAILURE
+        );
+        assert_eq!(
+            run(
+                &args(&["--min-files", "4"]),
+                &mut io::sink(),
+                &mut io::sink()
+            ),
+            exit::SUCCESS
+        );
+
+        let path = env::temp_dir().join("nodedup-fail-fast-baseline.json");
+        let path = path.to_str().unwrap();
+        let write = Args::try_parse_from([
+            "nodedup",
+            "./src/fixtures/stats",
+            "--output",
+            "none",
+            "--write-baseline",
+            path,
+        ])
+        .unwrap();
+        run(&write, &mut io::sink(), &mut io::sink());
+        assert_eq!(
+            run(
+                &args(&["--baseline", path]),
+                &mut io::sink(),
+                &mut io::sink()
+            ),
+            exit::SUCCESS
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_should_keep_fail_fast_message_off_stdout() {
+        let args = Args::try_parse_from([
+            "nodedup",
+            "./src/fixtures/stats",
+            "--output",
+            "json",
+            "--fail-fast",
+        ])
+        .unwrap();
+        let mut stdout: Vec<u8> = Vec::new();
+        let mut stderr: Vec<u8> = Vec::new();
+
+        run(&args, &mut stdout, &mut stderr);
+
+        assert!(stdout.is_empty());
+        assert!(String::from_utf8(stderr)
+            .unwrap()
+            .contains(", Second version found in "));
+    }
+
+    #[test]
+    fn it_should_reject_exit_options_with_fail_fast() {
+        for extra in [
+            ["--fail-on", "invalid"],
+            ["--silent-threshold", "1"],
+            ["--fail-threshold-percent", "10"],
+        ] {
+            let argv = [&["nodedup", "--fail-fast"][..], &extra[..]].concat();
+            assert!(Args::try_parse_from(argv).is_err());
+        }
+    }
+
+    #[test]
+    fn it_should_require_a_baseline_for_baseline_ignore_new() {
+        assert!(Args::try_parse_from(["nodedup", "--baseline-ignore-new"]).is_err());
+        assert!(Args::try_parse_from([
+            "nodedup",
+            "--baseline-ignore-new",
+            "--baseline",
+            "baseline.json"
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn it_should_exit_from_state_after_fix() {
+        let dir = env::temp_dir().join("nodedup-run-fix");
+        for (package, content) in [
+            ("a", r#"{ "dependencies": { "lodash": "^4.0.0" } }"#),
+            (
+                "b",
+                r#"{ "peerDependencies": { "lodash": "^3.0.0" }, "dependencies": { "lodash": "^3.0.0" } }"#,
+            ),
+        ] {
+            std::fs::create_dir_all(dir.join(package)).unwrap();
+            std::fs::write(dir.join(package).join("package.json"), content).unwrap();
+        }
+        let args = |extra: &[&str]| {
+            let mut argv = vec!["nodedup", dir.to_str().unwrap(), "--output", "none"];
+            argv.extend_from_slice(extra);
+            Args::try_parse_from(argv).unwrap()
+        };
+
+        assert_eq!(
+            run(&args(&["--fix"]), &mut io::sink(), &mut io::sink()),
+            exit::SUCCESS
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("b/package.json")).unwrap(),
+            r#"{ "peerDependencies": { "lodash": "^3.0.0" }, "dependencies": { "lodash": "^4.0.0" } }"#
+        );
+        assert_eq!(
+            run(&args(&[]), &mut io::sink(), &mut io::sink()),
+            exit::SUCCESS
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn it_should_keep_json_on_stdout_and_summary_on_stderr() {
+        let args =
+            Args::try_parse_from(["nodedup", "./src/fixtures/stats", "--output", "json"]).unwrap();
+        let mut stdout: Vec<u8> = Vec::new();
+        let mut stderr: Vec<u8> = Vec::new();
+
+        run(&args, &mut stdout, &mut stderr);
+
+        let report: serde_json::Value = serde_json::from_slice(&stdout).unwrap();
+        assert!(report.to_string().contains("lodash"));
+        assert!(String::from_utf8(stderr)
+            .unwrap()
+            .starts_with("nodedup: 2 duplicated deps in 3 files"));
+    }
+
+    #[test]
+    fn it_should_keep_json_output_parseable_with_extra_sections() {
+        let args = Args::try_parse_from([
+            "nodedup",
+            "./src/fixtures/stats",
+            "--output",
+            "json",
+            "--recommend",
+        ])
+        .unwrap();
+        let mut stdout: Vec<u8> = Vec::new();
+
+        run(&args, &mut stdout, &mut io::sink());
+
+        let report: serde_json::Value = serde_json::from_slice(&stdout).unwrap();
+        assert!(report.to_string().contains("react"));
+    }
+
+    #[test]
+    fn it_should_count_every_duplicate_with_top() {
+        let args = Args::try_parse_from([
+            "nodedup",
+            "./src/fixtures/stats",
+            "--count-only",
+            "--top",
+            "1",
+        ])
+        .unwrap();
+        let mut stdout: Vec<u8> = Vec::new();
+
+        run(&args, &mut stdout, &mut io::sink());
+
+        assert_eq!(String::from_utf8(stdout).unwrap(), "2\n");
+    }
+
+    #[test]
+    fn it_should_count_duplicates_with_all() {
+        let args =
+            Args::try_parse_from(["nodedup", "./src/fixtures/stats", "--count-only", "--all"])
+                .unwrap();
+        let mut stdout: Vec<u8> = Vec::new();
+
+        run(&args, &mut stdout, &mut io::sink());
+
+        assert_eq!(String::from_utf8(stdout).unwrap(), "2\n");
+    }
+
+    #[test]
+    fn it_should_show_duplicate_stats_with_all() {
+        let args =
+            Args::try_parse_from(["nodedup", "./src/fixtures/stats", "--stats", "--all"]).unwrap();
+        let mut stdout: Vec<u8> = Vec::new();
+
+        run(&args, &mut stdout, &mut io::sink());
+
+        assert!(String::from_utf8(stdout)
+            .unwrap()
+            .contains("Duplicated dependencies: 2\n"));
+    }
+
+    #[test]
+    fn it_should_show_relative_paths_for_a_single_file() {
+        let args = Args::try_parse_from([
+            "nodedup",
+            "./src/fixtures/single_file/package.json",
+            "--relative-paths",
+            "--output",
+            "json",
+        ])
+        .unwrap();
+        let mut stdout: Vec<u8> = Vec::new();
+
+        run(&args, &mut stdout, &mut io::sink());
+
+        let report: serde_json::Value = serde_json::from_slice(&stdout).unwrap();
+        assert_eq!(
+            report["duplicates"][0]["paths"],
+            serde_json::json!(["package.json", "package.json"])
+        );
+    }
+
+    #[test]
+    fn it_should_print_one_ndjson_line_per_group() {
+        let args =
+            |folder: &str| Args::try_parse_from(["nodedup", folder, "--output", "ndjson"]).unwrap();
+        let mut empty: Vec<u8> = Vec::new();
+        let mut stdout: Vec<u8> = Vec::new();
+
+        run(&args("./src/fixtures/stats/a"), &mut empty, &mut io::sink());
+        run(&args("./src/fixtures/stats"), &mut stdout, &mut io::sink());
+
+        assert_eq!(String::from_utf8(empty).unwrap(), "");
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.ends_with("}\n"));
+        assert_eq!(stdout.lines().count(), 2);
+        for line in stdout.lines() {
+            serde_json::from_str::<serde_json::Value>(line).unwrap();
+        }
+    }
+
+    #[test]
+    fn it_should_pass_against_fresh_baseline() {
+        let path = env::temp_dir().join("nodedup-run-baseline.json");
+        let path = path.to_str().unwrap();
+        let args = |flag: &str| {
+            Args::try_parse_from([
+                "nodedup",
+                "./src/fixtures/stats",
+                "--output",
+                "none",
+                flag,
+                path,
+            ])
+            .unwrap()
+        };
+
+        assert_ne!(
+            run(&args("--write-baseline"), &mut io::sink(), &mut io::sink()),
+            0
+        );
+        assert_eq!(
+            run(&args("--baseline"), &mut io::sink(), &mut io::sink()),
+            0
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_should_reject_both_folder_forms() {
+        assert!(Args::try_parse_from(["nodedup", "./a", "--folder", "./b"]).is_err());
+    }
+
+    #[test]
+    fn it_should_write_whole_lines_to_each_stream() {
+        let args =
+            Args::try_parse_from(["nodedup", "./src/fixtures/stats", "--output", "line"]).unwrap();
+        let mut stdout: Vec<u8> = Vec::new();
+        let mut stderr: Vec<u8> = Vec::new();
+
+        run(&args, &mut stdout, &mut stderr);
+
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert_eq!(stdout.lines().count(), 5);
+        for line in stdout.lines() {
+            let columns: Vec<&str> = line.split('\t').collect();
+            assert_eq!(columns.len(), 3, "garbled line: {}", line);
+            assert!(columns[2].ends_with("package.json"));
+        }
+        let stderr = String::from_utf8(stderr).unwrap();
+        assert_eq!(stderr.lines().count(), 1);
+        assert!(stderr.starts_with("nodedup: 2 duplicated deps in 3 files"));
+    }
 }
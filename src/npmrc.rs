@@ -0,0 +1,77 @@
+use std::{collections::HashMap, fs};
+
+use crate::parser::get_scope;
+
+const DEFAULT_REGISTRY: &str = "https://registry.npmjs.org/";
+
+#[derive(Debug, PartialEq, Default)]
+pub struct Registries {
+    default: Option<String>,
+    scopes: HashMap<String, String>,
+}
+
+impl Registries {
+    // Scoped packages use their scope's registry, everything else the default one
+    pub fn resolve(&self, name: &str) -> &str {
+        get_scope(name)
+            .and_then(|scope| self.scopes.get(scope))
+            .or(self.default.as_ref())
+            .map_or(DEFAULT_REGISTRY, String::as_str)
+    }
+}
+
+// Only `registry` and `@scope:registry` are read, auth tokens and other settings are skipped
+pub fn parse_npmrc(content: &str) -> Registries {
+    let mut registries = Registries::default();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match key.trim().split_once(':') {
+            None if key.trim() == "registry" => registries.default = Some(value),
+            Some((scope, "registry")) if scope.starts_with('@') => {
+                registries.scopes.insert(scope.to_string(), value);
+            }
+            _ => {}
+        }
+    }
+
+    registries
+}
+
+pub fn read_npmrc(path: &str) -> Registries {
+    let content = fs::read_to_string(path).unwrap_or_else(|_| {
+        panic!("Failed to read the .npmrc file: {}", path);
+    });
+
+    parse_npmrc(&content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_map_scopes_to_registries() {
+        let registries = read_npmrc("./src/fixtures/npmrc/.npmrc");
+
+        assert_eq!(registries.resolve("@acme/ui"), "https://npm.acme.dev/");
+        assert_eq!(
+            registries.resolve("@other/ui"),
+            "https://registry.example.com/"
+        );
+        assert_eq!(registries.resolve("react"), "https://registry.example.com/");
+        assert_eq!(registries.scopes.len(), 1);
+    }
+
+    #[test]
+    fn it_should_fall_back_to_npm_registry() {
+        let registries = parse_npmrc("# nothing here\nsave-exact=true\n");
+
+        assert_eq!(registries.resolve("@acme/ui"), DEFAULT_REGISTRY);
+    }
+}
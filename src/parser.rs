@@ -1,57 +1,381 @@
-use std::{collections::HashMap, fs, path::Path};
-
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use glob::Pattern;
+use serde::de::{Deserialize, Deserializer, IgnoredAny, MapAccess, Visitor};
 use serde_json::Value;
 
+use crate::{deno, diagnostics::Counters};
+
+const DEPENDENCY_SECTIONS: [&str; 2] = ["dependencies", "devDependencies"];
+const NON_REGISTRY_PREFIXES: [&str; 7] = [
+    "git+",
+    "git:",
+    "github:",
+    "gitlab:",
+    "bitbucket:",
+    "file:",
+    "link:",
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Spread {
+    Major(u64),
+    Minor(u64),
+    Patch(u64),
+}
+
+impl fmt::Display for Spread {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Spread::Major(distance) => write!(f, "{} major", distance),
+            Spread::Minor(distance) => write!(f, "{} minor", distance),
+            Spread::Patch(distance) => write!(f, "{} patch", distance),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Default)]
+pub enum Section {
+    #[default]
+    Dependencies,
+    DevDependencies,
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Section::Dependencies => write!(f, "dependencies"),
+            Section::DevDependencies => write!(f, "devDependencies"),
+        }
+    }
+}
+
+// Version parts below the chosen one are not compared, so `1.2.0` and `1.3.0` match under `major`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Granularity {
+    Major,
+    Minor,
+    #[default]
+    Patch,
+}
+
+// Git, URL and file specifiers carry no comparable version, so they are kept out of duplicate checks
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum VersionKind {
+    #[default]
+    Registry,
+    // Compound ranges such as `>=1.0.0 <2.0.0` or `1.x || 2.x` are kept verbatim
+    Range,
+    NonRegistry,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct PackageValue {
     pub name: String,
     pub version: String,
-    pub path: String,
+    pub range: String,
+    pub path: PathBuf,
+    pub specificity: usize,
+    pub section: Section,
+    pub kind: VersionKind,
+    // The `name` of the package.json declaring the dependency
+    pub package: Option<String>,
 }
 
 impl PackageValue {
-    pub fn new(name: &str, version: &str, path: &str) -> Self {
+    pub fn new(name: &str, version: &str, path: impl AsRef<Path>) -> Self {
         PackageValue {
             name: name.to_string(),
             version: version.to_string(),
-            path: path.to_string(),
+            range: version.to_string(),
+            path: path.as_ref().to_path_buf(),
+            specificity: get_specificity(version),
+            section: Section::default(),
+            kind: VersionKind::default(),
+            package: None,
+        }
+    }
+
+    pub fn with_range(mut self, range: &str) -> Self {
+        self.range = range.to_string();
+        self
+    }
+
+    pub fn with_section(mut self, section: Section) -> Self {
+        self.section = section;
+        self
+    }
+
+    pub fn with_kind(mut self, kind: VersionKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn with_package(mut self, package: Option<&str>) -> Self {
+        self.package = package.map(String::from);
+        self
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct BundledMismatch {
+    pub name: String,
+    pub path: PathBuf,
+    pub version: Option<String>,
+    pub elsewhere: Vec<PackageValue>,
+}
+
+// Counts cover only the files read by one call, even when the counters passed in are shared
+#[derive(Debug, Default, PartialEq)]
+pub struct ScanResult {
+    pub duplicates: HashMap<String, Vec<PackageValue>>,
+    pub files_scanned: usize,
+    pub files_skipped: usize,
+    pub errors: usize,
+}
+
+impl ScanResult {
+    fn new(
+        duplicates: HashMap<String, Vec<PackageValue>>,
+        before: &Counters,
+        after: &Counters,
+    ) -> Self {
+        ScanResult {
+            duplicates,
+            files_scanned: after.parsed - before.parsed,
+            files_skipped: after.skipped - before.skipped,
+            errors: after.invalid - before.invalid,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct CaseCollision {
+    pub path: PathBuf,
+    pub names: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct DuplicateKey {
+    pub name: String,
+    pub section: String,
+    pub path: PathBuf,
+}
+
+struct ManifestKeys(Vec<(String, String)>);
+
+struct SectionKeys(Vec<String>);
+
+impl<'de> Deserialize<'de> for ManifestKeys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ManifestVisitor;
+
+        impl<'de> Visitor<'de> for ManifestVisitor {
+            type Value = ManifestKeys;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a package.json object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut duplicates = Vec::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    if DEPENDENCY_SECTIONS.contains(&key.as_str()) {
+                        let section = map.next_value::<SectionKeys>()?;
+                        duplicates.extend(section.0.into_iter().map(|name| (key.clone(), name)));
+                    } else {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                }
+
+                Ok(ManifestKeys(duplicates))
+            }
+        }
+
+        deserializer.deserialize_map(ManifestVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for SectionKeys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SectionVisitor;
+
+        impl<'de> Visitor<'de> for SectionVisitor {
+            type Value = SectionKeys;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a dependencies object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut seen = HashSet::new();
+                let mut duplicates = Vec::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    map.next_value::<IgnoredAny>()?;
+                    if !seen.insert(key.clone()) {
+                        duplicates.push(key);
+                    }
+                }
+
+                Ok(SectionKeys(duplicates))
+            }
+        }
+
+        deserializer.deserialize_map(SectionVisitor)
+    }
+}
+
+#[derive(Debug)]
+enum ReadError {
+    Io(std::io::Error),
+    Utf8,
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "{}", e),
+            ReadError::Utf8 => write!(f, "not valid UTF-8"),
+            ReadError::Json(e) => write!(f, "invalid JSON at {}:{}", e.line(), e.column()),
         }
     }
 }
 
-fn parse_file(path: &Path) -> std::io::Result<Value> {
-    let res = fs::read_to_string(path)?;
-    let value: Value = serde_json::from_str(&res)?;
+fn read_package_json(path: &Path) -> Result<String, ReadError> {
+    let bytes = fs::read(path).map_err(ReadError::Io)?;
+    let content = String::from_utf8(bytes).map_err(|_| ReadError::Utf8)?;
+    let content = strip_bom(&content);
+
+    Ok(if deno::is_jsonc(path) {
+        deno::strip_jsonc(content)
+    } else {
+        content.to_string()
+    })
+}
+
+// Editors on Windows may prefix the file with a BOM, which serde_json rejects
+fn strip_bom(content: &str) -> &str {
+    content.strip_prefix('\u{feff}').unwrap_or(content)
+}
+
+fn parse_file(path: &Path) -> Result<Value, ReadError> {
+    let res = read_package_json(path)?;
 
-    Ok(value)
+    serde_json::from_str(&res).map_err(ReadError::Json)
 }
 
-fn build_hash_map(value: Value, path: &str, map: &mut HashMap<String, Vec<PackageValue>>) {
-    let deps = value.get("dependencies");
-    let dev_deps = value.get("devDependencies");
-    traverse_deps(deps, map, path);
-    traverse_deps(dev_deps, map, path);
+// Custom fields are JSON pointers such as `/acme:deps`, their entries count as dependencies
+// Without dedupe_same_version, a file declaring the same version in several sections keeps every
+// occurrence
+fn build_hash_map(
+    value: Value,
+    path: &Path,
+    fields: &[String],
+    dedupe_same_version: bool,
+    map: &mut HashMap<String, Vec<PackageValue>>,
+) {
+    let package = value.get("name").and_then(|n| n.as_str());
+    let sections = [
+        (value.get("dependencies"), Section::Dependencies),
+        (value.get("devDependencies"), Section::DevDependencies),
+    ];
+    let custom = fields
+        .iter()
+        .map(|field| (value.pointer(field), Section::Dependencies));
+    for (deps, section) in sections.into_iter().chain(custom) {
+        traverse_deps(deps, map, path, section, package, dedupe_same_version);
+    }
+    if deno::is_deno_manifest(path) {
+        deno::traverse_imports(value.get("imports"), map, path);
+    }
 }
 
-fn traverse_deps(deps: Option<&Value>, map: &mut HashMap<String, Vec<PackageValue>>, path: &str) {
+fn traverse_deps(
+    deps: Option<&Value>,
+    map: &mut HashMap<String, Vec<PackageValue>>,
+    path: &Path,
+    section: Section,
+    package: Option<&str>,
+    dedupe_same_version: bool,
+) {
     deps.and_then(|d| d.as_object())
         .into_iter()
         .flat_map(|deps| deps.iter())
-        .filter_map(|(key, value)| Some((key, value.as_str()?)))
-        .for_each(|(key, value_str)| process_dependency(key, value_str, map, path));
+        .filter_map(|(key, value)| match value.as_str() {
+            Some(value_str) => Some((key, value_str)),
+            None => {
+                warn_malformed(key, value, path);
+                None
+            }
+        })
+        .for_each(|(key, value_str)| {
+            let package_value =
+                dependency_value(key, value_str, path, section).with_package(package);
+            insert_value(key, package_value, map, dedupe_same_version);
+        });
+}
+
+// A version written as an object or a number is a mistake in the file, so it is not skipped quietly
+fn warn_malformed(key: &str, value: &Value, path: &Path) {
+    log::warn!(
+        "Skipping {} in {}: malformed version {}, expected a string",
+        key,
+        path.display(),
+        value
+    );
 }
 
-fn process_dependency(
+pub fn process_dependency(
     key: &str,
     value_str: &str,
     map: &mut HashMap<String, Vec<PackageValue>>,
-    path: &str,
+    path: &Path,
+    section: Section,
+) {
+    insert_value(
+        key,
+        dependency_value(key, value_str, path, section),
+        map,
+        true,
+    );
+}
+
+fn dependency_value(key: &str, value_str: &str, path: &Path, section: Section) -> PackageValue {
+    let kind = get_version_kind(value_str);
+    let version = get_comparable_version(value_str, kind);
+    PackageValue::new(key, &version, path)
+        .with_range(value_str)
+        .with_section(section)
+        .with_kind(kind)
+}
+
+fn insert_value(
+    key: &str,
+    package_value: PackageValue,
+    map: &mut HashMap<String, Vec<PackageValue>>,
+    dedupe_same_version: bool,
 ) {
     let entry = map.entry(key.to_string()).or_default();
-    let version = clean_version(value_str);
-    let package_value = PackageValue::new(key, &version, path);
+    let version = package_value.version.clone();
+
+    if dedupe_same_version
+        && entry
+            .iter()
+            .any(|v| v.version == version && v.path == package_value.path)
+    {
+        return;
+    }
 
-    if entry.iter().any(|v| v.version == version) {
+    // Occurrences of an already known version are kept next to it in first-seen order
+    if let Some(index) = entry.iter().rposition(|v| v.version == version) {
+        entry.insert(index + 1, package_value);
         return;
     }
 
@@ -64,6 +388,58 @@ fn process_dependency(
     }
 }
 
+pub fn get_comparable_version(value_str: &str, kind: VersionKind) -> String {
+    match kind {
+        VersionKind::Registry => clean_version(&normalize_version(value_str)),
+        VersionKind::Range => normalize_version(value_str.trim()),
+        VersionKind::NonRegistry => value_str.to_string(),
+    }
+}
+
+// Ranges never contain a slash, while URLs, paths and `org/repo` GitHub shorthands always do
+pub fn get_version_kind(value_str: &str) -> VersionKind {
+    let is_alias = value_str.starts_with("npm:");
+    if NON_REGISTRY_PREFIXES
+        .iter()
+        .any(|prefix| value_str.starts_with(prefix))
+        || (!is_alias && value_str.contains('/'))
+    {
+        VersionKind::NonRegistry
+    } else if is_compound_range(value_str) {
+        VersionKind::Range
+    } else {
+        VersionKind::Registry
+    }
+}
+
+// `>= 1.0.0` is a single comparator split by a space, so only tokens holding a number are counted
+fn is_compound_range(value_str: &str) -> bool {
+    value_str.contains("||")
+        || value_str
+            .split_whitespace()
+            .filter(|token| token.contains(|c: char| c.is_ascii_digit()))
+            .count()
+            > 1
+}
+
+// A `v` or `V` right before the number, as in `v1.2.3` or `>=v1.0.0 <v2.0.0`, is not part of the
+// version, so it is dropped before the version is read
+pub fn normalize_version(range: &str) -> String {
+    let mut normalized = String::with_capacity(range.len());
+    let mut previous: Option<char> = None;
+    let mut chars = range.chars().peekable();
+    while let Some(c) = chars.next() {
+        let starts_comparator = previous.is_none_or(|p| " =<>~^|".contains(p));
+        let precedes_number = chars.peek().is_some_and(char::is_ascii_digit);
+        if !(matches!(c, 'v' | 'V') && starts_comparator && precedes_number) {
+            normalized.push(c);
+        }
+        previous = Some(c);
+    }
+
+    normalized
+}
+
 fn clean_version(version_str: &str) -> String {
     version_str
         .chars()
@@ -76,340 +452,2322 @@ fn is_new_version_higher(version: &str, entry: &[PackageValue]) -> bool {
         return false;
     }
 
-    let (major, minor, patch) = get_versions(&entry[0].version);
-    let (major_new, minor_new, patch_new) = get_versions(version);
+    // `1` covers every 1.x.x release, so it ranks above an equal but more specific `1.0.0`
+    match compare_versions(version, &entry[0].version) {
+        Ordering::Greater => true,
+        Ordering::Equal => get_specificity(version) < entry[0].specificity,
+        Ordering::Less => false,
+    }
+}
 
-    major_new > major
-        || (major_new == major && minor_new > minor)
-        || (major_new == major && minor_new == minor && patch_new > patch)
+fn get_specificity(version: &str) -> usize {
+    version.split('.').filter(|part| !part.is_empty()).count()
 }
 
-fn get_versions(version: &str) -> (u32, u32, u32) {
+// Compound ranges are ordered by the version of their first comparator
+fn get_parts(version: &str) -> [&str; 3] {
+    let version = version
+        .trim_start_matches(|c: char| !c.is_ascii_digit())
+        .split([' ', '|'])
+        .next()
+        .unwrap_or_default();
     let mut parts = version.split('.');
-    let major = parts.next().unwrap_or("0").parse().unwrap_or(0);
-    let minor = parts.next().unwrap_or("0").parse().unwrap_or(0);
-    let patch = parts.next().unwrap_or("0").parse().unwrap_or(0);
+    [(); 3].map(|_| parts.next().unwrap_or(""))
+}
+
+// A part too large for u64 saturates, so it still ranks above every part that fits
+fn parse_part(part: &str) -> u64 {
+    match part.parse() {
+        Ok(number) => number,
+        Err(_) if is_number(part) => u64::MAX,
+        Err(_) => 0,
+    }
+}
+
+fn is_number(part: &str) -> bool {
+    !part.is_empty() && part.chars().all(|c| c.is_ascii_digit())
+}
+
+pub fn get_versions(version: &str) -> (u64, u64, u64) {
+    let [major, minor, patch] = get_parts(version).map(parse_part);
 
     (major, minor, patch)
 }
 
+// Digit strings are compared by length and then by their digits, which stays correct for
+// numbers that overflow u64
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    get_parts(a)
+        .iter()
+        .zip(get_parts(b))
+        .map(|(a, b)| {
+            if is_number(a) && is_number(b) {
+                let a = a.trim_start_matches('0');
+                let b = b.trim_start_matches('0');
+                a.len().cmp(&b.len()).then(a.cmp(b))
+            } else {
+                parse_part(a).cmp(&parse_part(b))
+            }
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
 pub fn find_duplicate_dependencies(
-    paths: Vec<String>,
+    paths: Vec<PathBuf>,
     ignores: &[String],
+    fields: &[String],
+    counters: &mut Counters,
+) -> ScanResult {
+    let before = counters.clone();
+    let mut hash_map = collect_dependencies(&paths, fields, counters);
+    keep_bad_values(&mut hash_map, ignores);
+
+    ScanResult::new(hash_map, &before, counters)
+}
+
+// Same as find_duplicate_dependencies, but a version declared in several sections of one file is
+// listed once per section, so every occurrence can be checked when debugging
+pub fn find_every_occurrence(
+    paths: Vec<PathBuf>,
+    ignores: &[String],
+    fields: &[String],
+    counters: &mut Counters,
+) -> ScanResult {
+    let before = counters.clone();
+    let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+    for path in &paths {
+        if let Some(value) = read_dependencies_file(path, counters) {
+            build_hash_map(value, path, fields, false, &mut hash_map);
+        }
+    }
+    keep_bad_values(&mut hash_map, ignores);
+
+    ScanResult::new(hash_map, &before, counters)
+}
+
+// Same as find_duplicate_dependencies for package.json contents that are already in memory
+pub fn find_duplicates_in_contents(
+    contents: &[(PathBuf, String)],
+    ignores: &[String],
+    fields: &[String],
+    counters: &mut Counters,
+) -> ScanResult {
+    let before = counters.clone();
+    let mut hash_map = collect_dependencies_from_contents(contents, fields, counters);
+    keep_bad_values(&mut hash_map, ignores);
+
+    ScanResult::new(hash_map, &before, counters)
+}
+
+pub fn collect_dependencies_from_contents(
+    contents: &[(PathBuf, String)],
+    fields: &[String],
+    counters: &mut Counters,
 ) -> HashMap<String, Vec<PackageValue>> {
     let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
-    for path in paths {
-        let path_buf = Path::new(&path);
-        let value = parse_file(path_buf).unwrap();
-        build_hash_map(value, &path, &mut hash_map);
+    for (path, content) in contents {
+        let value: Value = match serde_json::from_str(strip_bom(content)) {
+            Ok(value) => value,
+            Err(e) => {
+                skip_unreadable(path, &ReadError::Json(e), counters);
+                continue;
+            }
+        };
+        if !value.is_object() {
+            skip_non_object(path, counters);
+            continue;
+        }
+        counters.parsed += 1;
+        build_hash_map(value, path, fields, true, &mut hash_map);
     }
+
+    hash_map
+}
+
+pub fn find_case_insensitive_duplicates(
+    paths: Vec<PathBuf>,
+    ignores: &[String],
+    fields: &[String],
+    counters: &mut Counters,
+) -> ScanResult {
+    let before = counters.clone();
+    let mut hash_map = merge_case_insensitive(collect_dependencies(&paths, fields, counters));
     keep_bad_values(&mut hash_map, ignores);
 
+    ScanResult::new(hash_map, &before, counters)
+}
+
+// Ignores match package names, so they are applied before the section is added to the key
+pub fn find_duplicates_by_section(
+    paths: Vec<PathBuf>,
+    ignores: &[String],
+    fields: &[String],
+    counters: &mut Counters,
+) -> ScanResult {
+    let before = counters.clone();
+    let mut hash_map = collect_dependencies(&paths, fields, counters);
+    hash_map.retain(|name, _| !is_ignored(name, ignores));
+    let mut hash_map = split_by_section(hash_map);
+    keep_bad_values(&mut hash_map, &[]);
+
+    ScanResult::new(hash_map, &before, counters)
+}
+
+// Groups are keyed as `name (section)`, so versions are only compared within one section
+fn split_by_section(
+    hash_map: HashMap<String, Vec<PackageValue>>,
+) -> HashMap<String, Vec<PackageValue>> {
+    let mut split: HashMap<String, Vec<PackageValue>> = HashMap::new();
+    for (name, values) in hash_map {
+        for value in values {
+            let key = format!("{} ({})", name, value.section);
+            split.entry(key).or_default().push(value);
+        }
+    }
+
+    split
+}
+
+// Groups are keyed by the lowercased name, each value keeps the spelling from its package.json
+fn merge_case_insensitive(
+    hash_map: HashMap<String, Vec<PackageValue>>,
+) -> HashMap<String, Vec<PackageValue>> {
+    let mut names: Vec<&String> = hash_map.keys().collect();
+    names.sort();
+
+    let mut merged: HashMap<String, Vec<PackageValue>> = HashMap::new();
+    for name in names {
+        for value in &hash_map[name] {
+            insert_value(&name.to_lowercase(), value.clone(), &mut merged, true);
+        }
+    }
+
+    merged
+}
+
+pub fn find_case_conflicts(
+    dependencies: &HashMap<String, Vec<PackageValue>>,
+) -> BTreeMap<String, Vec<String>> {
+    let mut spellings: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for name in dependencies.keys() {
+        spellings
+            .entry(name.to_lowercase())
+            .or_default()
+            .push(name.clone());
+    }
+    spellings.retain(|_, names| names.len() > 1);
+    spellings.values_mut().for_each(|names| names.sort());
+
+    spellings
+}
+
+pub fn collect_dependencies(
+    paths: &[PathBuf],
+    fields: &[String],
+    counters: &mut Counters,
+) -> HashMap<String, Vec<PackageValue>> {
+    let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+    for path in paths {
+        if let Some(value) = read_dependencies_file(path, counters) {
+            build_hash_map(value, path, fields, true, &mut hash_map);
+        }
+    }
+
     hash_map
 }
 
-fn keep_bad_values(hash_map: &mut HashMap<String, Vec<PackageValue>>, ignores: &[String]) {
-    let keys_to_remove: Vec<String> = hash_map
-        .iter()
-        .filter_map(|(key, values)| {
-            let ignored = ignores.iter().any(|i| i == key);
-            if values.len() > 1 && !ignored {
-                None
-            } else {
-                Some(key.clone())
-            }
-        })
-        .collect();
+// Files that cannot be read as a package.json object are counted and skipped with a warning
+fn read_dependencies_file(path: &Path, counters: &mut Counters) -> Option<Value> {
+    let value = match parse_file(path) {
+        Ok(value) => value,
+        Err(e) => {
+            skip_unreadable(path, &e, counters);
+            return None;
+        }
+    };
+    if !value.is_object() {
+        skip_non_object(path, counters);
+        return None;
+    }
+    counters.parsed += 1;
 
-    for key in keys_to_remove {
-        hash_map.remove(&key);
+    Some(value)
+}
+
+// Stops at the file that gives a package its second version, the files after it are not read
+pub fn find_first_duplicate(
+    paths: &[PathBuf],
+    ignores: &[String],
+    fields: &[String],
+    counters: &mut Counters,
+    is_failing: impl Fn(&str, &[PackageValue]) -> bool,
+) -> Option<(String, PathBuf)> {
+    let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+    for path in paths {
+        let Some(value) = read_dependencies_file(path, counters) else {
+            continue;
+        };
+        build_hash_map(value, path, fields, true, &mut hash_map);
+        let duplicate = hash_map
+            .iter()
+            .filter(|(name, values)| {
+                let versions: HashSet<&String> = values
+                    .iter()
+                    .filter(|v| v.kind != VersionKind::NonRegistry)
+                    .map(|v| &v.version)
+                    .collect();
+                versions.len() > 1 && !is_ignored(name, ignores) && is_failing(name, values)
+            })
+            .map(|(name, _)| name)
+            .min();
+        if let Some(name) = duplicate {
+            return Some((name.clone(), path.clone()));
+        }
     }
+
+    None
 }
 
-fn read_ignores(path: &str) -> std::io::Result<String> {
-    let file = fs::read_to_string(path)?;
+fn skip_unreadable(path: &Path, error: &ReadError, counters: &mut Counters) {
+    log::warn!("Skipping {}: {}", path.display(), error);
+    counters.skipped += 1;
+    counters.invalid += 1;
+}
 
-    Ok(file)
+// Valid JSON that is not an object, e.g. an array, has no dependency sections to read
+fn skip_non_object(path: &Path, counters: &mut Counters) {
+    log::warn!("Skipping {}: root is not a JSON object", path.display());
+    counters.skipped += 1;
+    counters.invalid += 1;
+    counters.not_object += 1;
 }
 
-fn parse_ignores(ignores: &str) -> Vec<String> {
-    ignores.lines().map(|s| s.trim().to_string()).collect()
+// npm treats the spellings as separate packages that install into the same folder
+// on case-insensitive file systems, so one file declaring both is always a mistake
+pub fn find_case_collisions(
+    dependencies: &HashMap<String, Vec<PackageValue>>,
+) -> Vec<CaseCollision> {
+    let mut spellings: BTreeMap<(&Path, String), BTreeSet<&str>> = BTreeMap::new();
+    for (name, values) in dependencies {
+        for value in values {
+            spellings
+                .entry((&value.path, name.to_lowercase()))
+                .or_default()
+                .insert(name);
+        }
+    }
+
+    spellings
+        .into_iter()
+        .filter(|(_, names)| names.len() > 1)
+        .map(|((path, _), names)| CaseCollision {
+            path: path.to_path_buf(),
+            names: names.into_iter().map(String::from).collect(),
+        })
+        .collect()
 }
 
-pub fn get_ignore_values(path: &str) -> Vec<String> {
-    let ignore_file = read_ignores(path).unwrap_or_default();
-    parse_ignores(&ignore_file)
+pub fn find_duplicate_keys(paths: &[PathBuf]) -> Vec<DuplicateKey> {
+    paths
+        .iter()
+        .flat_map(|path| {
+            let content = read_package_json(path).unwrap_or_default();
+            get_duplicate_keys(&content, path)
+        })
+        .collect()
 }
 
-#[cfg(test)]
-mod tests {
-    use std::path::PathBuf;
+fn get_duplicate_keys(content: &str, path: &Path) -> Vec<DuplicateKey> {
+    // serde_json keeps only the last value of a repeated key, so the raw map is walked instead
+    let keys: ManifestKeys = match serde_json::from_str(content) {
+        Ok(keys) => keys,
+        Err(_) => return vec![],
+    };
 
-    use super::*;
+    keys.0
+        .into_iter()
+        .map(|(section, name)| DuplicateKey {
+            name,
+            section,
+            path: path.to_path_buf(),
+        })
+        .collect()
+}
 
-    #[test]
-    fn it_should_read_package_json() {
-        let path = PathBuf::from("./src/data/package.json");
+// Names declared by the scanned package.json files themselves, i.e. the local workspaces
+pub fn collect_package_names(paths: &[PathBuf]) -> HashSet<String> {
+    paths
+        .iter()
+        .filter_map(|path| parse_file(path).ok())
+        .filter_map(|value| Some(value.get("name")?.as_str()?.to_string()))
+        .collect()
+}
 
-        let actual = parse_file(&path).unwrap();
+// A local workspace referenced with `workspace:` in one file and with a registry range in another
+pub fn find_workspace_protocol_mixes(
+    dependencies: &HashMap<String, Vec<PackageValue>>,
+    package_names: &HashSet<String>,
+) -> BTreeMap<String, Vec<PackageValue>> {
+    package_names
+        .iter()
+        .filter_map(|name| {
+            let values = dependencies.get(name)?;
+            let is_workspace = |v: &&PackageValue| v.range.starts_with("workspace:");
+            let mixed =
+                values.iter().any(|v| is_workspace(&v)) && values.iter().any(|v| !is_workspace(&v));
+            mixed.then(|| (name.clone(), values.clone()))
+        })
+        .collect()
+}
 
-        let expected = r#"{
-          "dependencies": {
-            "mongoose": "^1.0.0"
-          }
-        }"#;
-        let expected: Value = serde_json::from_str(expected).unwrap();
+pub fn find_missing_dependencies(
+    paths: &[PathBuf],
+    dependencies: &HashMap<String, Vec<PackageValue>>,
+    names: &[String],
+) -> BTreeMap<String, Vec<PathBuf>> {
+    names
+        .iter()
+        .map(|name| {
+            let declared = dependencies
+                .get(name)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let missing = paths
+                .iter()
+                .filter(|path| !declared.iter().any(|v| &v.path == *path))
+                .cloned()
+                .collect();
+            (name.clone(), missing)
+        })
+        .collect()
+}
 
-        assert_eq!(actual, expected);
+pub fn find_bundled_mismatches(paths: &[PathBuf]) -> Vec<BundledMismatch> {
+    let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+    let mut bundled: Vec<BundledMismatch> = Vec::new();
+    for path in paths {
+        let Ok(value) = parse_file(path) else {
+            continue;
+        };
+        bundled.extend(collect_bundled(&value, path));
+        build_hash_map(value, path, &[], true, &mut hash_map);
     }
 
-    #[test]
-    fn it_should_read_ignore_file() {
-        let actual = read_ignores("./src/data/.ndignore").unwrap();
+    cross_reference_bundled(bundled, &hash_map)
+}
+
+fn collect_bundled(value: &Value, path: &Path) -> Vec<BundledMismatch> {
+    let bundled = value
+        .get("bundledDependencies")
+        .or_else(|| value.get("bundleDependencies"));
+    let names: Vec<&str> = match bundled {
+        Some(Value::Array(names)) => names.iter().filter_map(|n| n.as_str()).collect(),
+        // `true` bundles every production dependency
+        Some(Value::Bool(true)) => value
+            .get("dependencies")
+            .and_then(|d| d.as_object())
+            .map(|d| d.keys().map(|k| k.as_str()).collect())
+            .unwrap_or_default(),
+        _ => vec![],
+    };
+
+    names
+        .into_iter()
+        .map(|name| BundledMismatch {
+            name: name.to_string(),
+            path: path.to_path_buf(),
+            version: DEPENDENCY_SECTIONS
+                .iter()
+                .find_map(|section| value.get(section)?.get(name)?.as_str())
+                .map(|range| get_comparable_version(range, get_version_kind(range))),
+            elsewhere: vec![],
+        })
+        .collect()
+}
+
+fn cross_reference_bundled(
+    bundled: Vec<BundledMismatch>,
+    hash_map: &HashMap<String, Vec<PackageValue>>,
+) -> Vec<BundledMismatch> {
+    bundled
+        .into_iter()
+        .filter_map(|mut bundled| {
+            bundled.elsewhere = hash_map
+                .get(&bundled.name)?
+                .iter()
+                .filter(|v| v.path != bundled.path)
+                .filter(|v| Some(&v.version) != bundled.version.as_ref())
+                .cloned()
+                .collect();
+            if bundled.elsewhere.is_empty() {
+                None
+            } else {
+                Some(bundled)
+            }
+        })
+        .collect()
+}
+
+fn keep_bad_values(hash_map: &mut HashMap<String, Vec<PackageValue>>, ignores: &[String]) {
+    for values in hash_map.values_mut() {
+        values.retain(|v| v.kind != VersionKind::NonRegistry);
+    }
+    let keys_to_remove: Vec<String> = hash_map
+        .iter()
+        .filter_map(|(key, values)| {
+            if count_versions(values) > 1 && !is_ignored(key, ignores) {
+                None
+            } else {
+                Some(key.clone())
+            }
+        })
+        .collect();
+
+    for key in keys_to_remove {
+        hash_map.remove(&key);
+    }
+}
+
+// Every dependency and version seen, the way keep_bad_values would see them before filtering
+pub fn get_inventory(
+    dependencies: &HashMap<String, Vec<PackageValue>>,
+    ignores: &[String],
+) -> HashMap<String, Vec<PackageValue>> {
+    dependencies
+        .iter()
+        .filter(|(name, _)| !is_ignored(name, ignores))
+        .map(|(name, values)| (name.clone(), values.clone()))
+        .collect()
+}
+
+// Groups keep their highest version first, which is the natural target to standardize on
+pub fn get_recommendations(
+    duplicates: &HashMap<String, Vec<PackageValue>>,
+) -> BTreeMap<String, String> {
+    duplicates
+        .iter()
+        .filter_map(|(name, values)| Some((name.clone(), values.first()?.version.clone())))
+        .collect()
+}
+
+pub fn find_non_registry_dependencies(
+    dependencies: &HashMap<String, Vec<PackageValue>>,
+) -> Vec<PackageValue> {
+    let mut values: Vec<PackageValue> = dependencies
+        .values()
+        .flatten()
+        .filter(|v| v.kind == VersionKind::NonRegistry)
+        .cloned()
+        .collect();
+    values.sort_by(|a, b| (&a.name, &a.path).cmp(&(&b.name, &b.path)));
+
+    values
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SameVersionGroup {
+    pub name: String,
+    pub range: String,
+    pub files: usize,
+}
+
+// Groups with a single version never become duplicates, they are listed for inventory only
+pub fn find_same_version_groups(
+    dependencies: &HashMap<String, Vec<PackageValue>>,
+    ignores: &[String],
+) -> Vec<SameVersionGroup> {
+    let mut groups: Vec<SameVersionGroup> = dependencies
+        .iter()
+        .filter(|(name, values)| {
+            count_versions(values) == 1 && count_files(values) > 1 && !is_ignored(name, ignores)
+        })
+        .map(|(name, values)| SameVersionGroup {
+            name: name.clone(),
+            range: values[0].range.clone(),
+            files: count_files(values),
+        })
+        .collect();
+    groups.sort_by(|a, b| a.name.cmp(&b.name));
+
+    groups
+}
+
+pub fn is_ignored(name: &str, ignores: &[String]) -> bool {
+    ignores
+        .iter()
+        .any(|i| i == name || Pattern::new(i).is_ok_and(|p| p.matches(name)))
+}
+
+// A scope is excluded by ignoring every name under it
+pub fn get_scope_ignores(scopes: &[String]) -> Vec<String> {
+    scopes
+        .iter()
+        .map(|scope| format!("@{}/*", scope.trim_start_matches('@')))
+        .collect()
+}
+
+pub fn is_dev_only(values: &[PackageValue]) -> bool {
+    values.iter().all(|v| v.section == Section::DevDependencies)
+}
+
+pub fn retain_by_granularity(
+    duplicates: &mut HashMap<String, Vec<PackageValue>>,
+    granularity: Granularity,
+) {
+    if granularity == Granularity::Patch {
+        return;
+    }
+    duplicates.retain(|_, values| {
+        values
+            .iter()
+            .map(|v| {
+                let (major, minor, _) = get_versions(&v.version);
+                match granularity {
+                    Granularity::Major => (major, 0),
+                    _ => (major, minor),
+                }
+            })
+            .collect::<HashSet<(u64, u64)>>()
+            .len()
+            > 1
+    });
+}
+
+// A file declaring the package in both sections is counted once
+pub fn count_files(values: &[PackageValue]) -> usize {
+    values
+        .iter()
+        .map(|v| &v.path)
+        .collect::<HashSet<&PathBuf>>()
+        .len()
+}
+
+pub fn retain_by_min_files(duplicates: &mut HashMap<String, Vec<PackageValue>>, min_files: usize) {
+    duplicates.retain(|_, values| count_files(values) >= min_files);
+}
+
+// Canary releases such as `1.0.0-next.5` are dropped, and so are groups left with one version
+pub fn drop_prereleases(duplicates: &mut HashMap<String, Vec<PackageValue>>) {
+    for values in duplicates.values_mut() {
+        values.retain(|v| !is_prerelease(&v.range));
+    }
+    duplicates.retain(|_, values| count_versions(values) > 1);
+}
+
+fn is_prerelease(range: &str) -> bool {
+    normalize_version(range)
+        .split_whitespace()
+        .map(|comparator| comparator.trim_start_matches(|c: char| !c.is_ascii_digit()))
+        .filter_map(|version| semver::Version::parse(version).ok())
+        .any(|version| !version.pre.is_empty())
+}
+
+pub fn count_versions(values: &[PackageValue]) -> usize {
+    values
+        .iter()
+        .map(|v| &v.version)
+        .collect::<HashSet<&String>>()
+        .len()
+}
+
+// Only the most significant differing part counts: 1.9.0 to 2.0.0 is a major step, not 9 minors
+pub fn get_spread(values: &[PackageValue]) -> Spread {
+    let versions = values.iter().map(|v| v.version.as_str());
+    let highest = versions.clone().max_by(|a, b| compare_versions(a, b));
+    let lowest = versions.min_by(|a, b| compare_versions(a, b));
+    let highest = get_versions(highest.unwrap_or_default());
+    let lowest = get_versions(lowest.unwrap_or_default());
+
+    if highest.0 != lowest.0 {
+        Spread::Major(highest.0.abs_diff(lowest.0))
+    } else if highest.1 != lowest.1 {
+        Spread::Minor(highest.1.abs_diff(lowest.1))
+    } else {
+        Spread::Patch(highest.2.abs_diff(lowest.2))
+    }
+}
+
+pub fn get_scope(name: &str) -> Option<&str> {
+    if !name.starts_with('@') {
+        return None;
+    }
+
+    name.split_once('/').map(|(scope, _)| scope)
+}
+
+fn read_ignores(path: &str) -> std::io::Result<String> {
+    let file = fs::read_to_string(path)?;
+
+    Ok(file)
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct IgnoreEntry {
+    pub pattern: String,
+    pub reason: Option<String>,
+}
+
+// The comment after a pattern is kept as the reason it is ignored.
+// Splitting on both characters covers LF, CRLF and CR-only files alike
+fn parse_ignores(ignores: &str) -> Vec<IgnoreEntry> {
+    ignores
+        .split(['\r', '\n'])
+        .filter_map(|line| {
+            let (pattern, comment) = line.split_once('#').unwrap_or((line, ""));
+            let pattern = pattern.trim();
+            let comment = comment.trim();
+            (!pattern.is_empty()).then(|| IgnoreEntry {
+                pattern: pattern.to_string(),
+                reason: (!comment.is_empty()).then(|| comment.to_string()),
+            })
+        })
+        .collect()
+}
+
+pub fn get_ignore_entries(path: &str) -> Vec<IgnoreEntry> {
+    let ignore_file = read_ignores(path).unwrap_or_default();
+    parse_ignores(&ignore_file)
+}
+
+pub fn get_ignore_values(path: &str) -> Vec<String> {
+    get_ignore_entries(path)
+        .into_iter()
+        .map(|entry| entry.pattern)
+        .collect()
+}
+
+// Entries given on the command line add to the ignore file unless they are meant to replace it
+pub fn merge_ignores(
+    file_ignores: Vec<String>,
+    cli_ignores: &[String],
+    cli_only: bool,
+) -> Vec<String> {
+    let file_ignores = if cli_only { vec![] } else { file_ignores };
+
+    [file_ignores, cli_ignores.to_vec()].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use log::Level;
+
+    use super::*;
+    use crate::test_logger;
+
+    #[test]
+    fn it_should_read_package_json() {
+        let path = PathBuf::from("./src/data/package.json");
+
+        let actual = parse_file(&path).unwrap();
+
+        let expected = r#"{
+          "dependencies": {
+            "mongoose": "^1.0.0"
+          }
+        }"#;
+        let expected: Value = serde_json::from_str(expected).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn it_should_strip_byte_order_mark() {
+        let path = PathBuf::from("./src/fixtures/encoding/bom/package.json");
+
+        let actual = parse_file(&path).unwrap();
+
+        assert_eq!(actual["dependencies"]["react"], "^18.0.0");
+    }
+
+    #[test]
+    fn it_should_keep_scanned_paths() {
+        let paths = vec![PathBuf::from("./src/fixtures/encoding/bom/package.json")];
+
+        let actual = collect_dependencies(&paths, &[], &mut Counters::default());
+
+        assert_eq!(actual["react"][0].path, paths[0]);
+    }
+
+    #[test]
+    fn it_should_skip_files_with_invalid_utf8() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/encoding/bom/package.json"),
+            PathBuf::from("./src/fixtures/encoding/invalid/package.json"),
+        ];
+        let mut counters = Counters::default();
+
+        let actual = collect_dependencies(&paths, &[], &mut counters);
+
+        assert_eq!(actual["react"].len(), 1);
+        assert_eq!(counters.parsed, 1);
+        assert_eq!(counters.skipped, 1);
+        assert_eq!(counters.invalid, 1);
+    }
+
+    #[test]
+    fn it_should_skip_files_with_invalid_json() {
+        test_logger::init();
+        let paths = vec![
+            PathBuf::from("./src/fixtures/encoding/bom/package.json"),
+            PathBuf::from("./src/fixtures/invalid_json/trailing_comma/package.json"),
+            PathBuf::from("./src/fixtures/invalid_json/truncated/package.json"),
+        ];
+        let mut counters = Counters::default();
+
+        let actual = collect_dependencies(&paths, &[], &mut counters);
+
+        assert_eq!(actual["react"].len(), 1);
+        assert_eq!(counters.parsed, 1);
+        assert_eq!(counters.skipped, 2);
+        assert_eq!(counters.invalid, 2);
+        assert!(test_logger::contains(
+            Level::Warn,
+            "Skipping ./src/fixtures/invalid_json/trailing_comma/package.json: invalid JSON at 4:3"
+        ));
+        assert!(test_logger::contains(
+            Level::Warn,
+            "Skipping ./src/fixtures/invalid_json/truncated/package.json: invalid JSON at 3:22"
+        ));
+    }
+
+    #[test]
+    fn it_should_warn_about_invalid_utf8() {
+        test_logger::init();
+        let path = PathBuf::from("./src/fixtures/encoding/invalid/package.json");
+
+        collect_dependencies(&[path], &[], &mut Counters::default());
+
+        assert!(test_logger::contains(
+            Level::Warn,
+            "Skipping ./src/fixtures/encoding/invalid/package.json: not valid UTF-8"
+        ));
+    }
+
+    #[test]
+    fn it_should_count_files_in_scan_result() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/stats/a/package.json"),
+            PathBuf::from("./src/fixtures/stats/b/package.json"),
+            PathBuf::from("./src/fixtures/encoding/invalid/package.json"),
+            PathBuf::from("./src/fixtures/root_type/array/package.json"),
+        ];
+        let mut counters = Counters {
+            discovered: 4,
+            ..Counters::default()
+        };
+
+        let result = find_duplicate_dependencies(paths, &[], &[], &mut counters);
+
+        let mut names: Vec<&String> = result.duplicates.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["lodash", "react"]);
+        assert_eq!(result.files_scanned, 2);
+        assert_eq!(result.files_skipped, 2);
+        assert_eq!(result.errors, 2);
+        assert_eq!(counters.discovered, 4);
+    }
+
+    #[test]
+    fn it_should_skip_files_with_non_object_root() {
+        test_logger::init();
+        let paths = vec![
+            PathBuf::from("./src/fixtures/encoding/bom/package.json"),
+            PathBuf::from("./src/fixtures/root_type/array/package.json"),
+            PathBuf::from("./src/fixtures/root_type/string/package.json"),
+        ];
+        let mut counters = Counters::default();
+
+        let actual = collect_dependencies(&paths, &[], &mut counters);
+
+        assert_eq!(actual["react"].len(), 1);
+        assert_eq!(counters.parsed, 1);
+        assert_eq!(counters.skipped, 2);
+        assert_eq!(counters.invalid, 2);
+        assert_eq!(counters.not_object, 2);
+        assert!(test_logger::contains(
+            Level::Warn,
+            "Skipping ./src/fixtures/root_type/array/package.json: root is not a JSON object"
+        ));
+        assert!(test_logger::contains(
+            Level::Warn,
+            "Skipping ./src/fixtures/root_type/string/package.json: root is not a JSON object"
+        ));
+    }
+
+    #[test]
+    fn it_should_skip_contents_with_non_object_root() {
+        let contents = vec![(PathBuf::from("./package.json"), "[]".to_string())];
+        let mut counters = Counters::default();
+
+        let actual = collect_dependencies_from_contents(&contents, &[], &mut counters);
+
+        assert!(actual.is_empty());
+        assert_eq!(counters.not_object, 1);
+    }
+
+    #[test]
+    fn it_should_read_ignore_file() {
+        let actual = read_ignores("./src/data/.ndignore").unwrap();
+
+        let expected = "testignore\ntestignore2\ntestignore3";
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn it_should_warn_about_non_string_versions() {
+        test_logger::init();
+        let json = r#"{
+          "dependencies": {
+            "mongoose": "^1.0.0",
+            "lodash": { "version": "4.0.0" }
+          },
+          "devDependencies": {
+            "react": 18
+          }
+        }"#;
+        let parsed: Value = serde_json::from_str(json).unwrap();
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        build_hash_map(
+            parsed,
+            Path::new("./malformed/package.json"),
+            &[],
+            true,
+            &mut hash_map,
+        );
+
+        assert_eq!(hash_map.keys().collect::<Vec<&String>>(), vec!["mongoose"]);
+        assert!(test_logger::contains(
+            Level::Warn,
+            r#"Skipping lodash in ./malformed/package.json: malformed version {"version":"4.0.0"}, expected a string"#
+        ));
+        assert!(test_logger::contains(
+            Level::Warn,
+            "Skipping react in ./malformed/package.json: malformed version 18, expected a string"
+        ));
+    }
+
+    #[test]
+    fn it_should_build_hash_map() {
+        let json = r#"{
+          "dependencies": {
+            "mongoose": "^1.0.0"
+          }
+        }"#;
+        let parsed: Value = serde_json::from_str(json).unwrap();
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        build_hash_map(parsed, Path::new(""), &[], true, &mut hash_map);
+
+        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        result_hash_map.insert(
+            "mongoose".to_string(),
+            vec![PackageValue::new("mongoose", "1.0.0", "").with_range("^1.0.0")],
+        );
+        assert_eq!(hash_map, result_hash_map);
+    }
+
+    #[test]
+    fn it_should_add_two_values() {
+        let json1 = r#"{
+          "dependencies": {
+            "mongoose": "^1.0.0"
+          }
+        }"#;
+        let json2 = r#"{
+          "dependencies": {
+            "mongoose": "2.0.0"
+          }
+        }"#;
+        let parsed1: Value = serde_json::from_str(json1).unwrap();
+        let parsed2: Value = serde_json::from_str(json2).unwrap();
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        build_hash_map(parsed1, Path::new(""), &[], true, &mut hash_map);
+        build_hash_map(parsed2, Path::new(""), &[], true, &mut hash_map);
+
+        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        result_hash_map.insert(
+            "mongoose".to_string(),
+            vec![
+                PackageValue::new("mongoose", "2.0.0", ""),
+                PackageValue::new("mongoose", "1.0.0", "").with_range("^1.0.0"),
+            ],
+        );
+
+        assert_eq!(hash_map, result_hash_map);
+    }
+
+    #[test]
+    fn it_should_call_all_together() {
+        let path = PathBuf::from("./src/data/package.json");
+        let result =
+            find_duplicate_dependencies(vec![path], &[], &[], &mut Counters::default()).duplicates;
+
+        assert_eq!(result, HashMap::new());
+    }
+
+    #[test]
+    fn it_should_collect_all_dependencies() {
+        let paths = [
+            PathBuf::from("./src/fixtures/stats/a/package.json"),
+            PathBuf::from("./src/fixtures/stats/b/package.json"),
+            PathBuf::from("./src/fixtures/stats/c/package.json"),
+        ];
+        let result = collect_dependencies(&paths, &[], &mut Counters::default());
+
+        assert_eq!(result.len(), 3);
+        let duplicates =
+            find_duplicate_dependencies(paths.to_vec(), &[], &[], &mut Counters::default())
+                .duplicates;
+        assert_eq!(duplicates.len(), 2);
+    }
+
+    #[test]
+    fn it_should_also_parse_dev_dependencies() {
+        let json1 = r#"{
+          "dependencies": {
+            "mongoose": "^1.0.0"
+          }
+        }"#;
+        let json2 = r#"{
+          "devDependencies": {
+            "mongoose": "2.0.0"
+          }
+        }"#;
+        let parsed1: Value = serde_json::from_str(json1).unwrap();
+        let parsed2: Value = serde_json::from_str(json2).unwrap();
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        build_hash_map(parsed1, Path::new(""), &[], true, &mut hash_map);
+        build_hash_map(parsed2, Path::new(""), &[], true, &mut hash_map);
+
+        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        result_hash_map.insert(
+            "mongoose".to_string(),
+            vec![
+                PackageValue::new("mongoose", "2.0.0", "").with_section(Section::DevDependencies),
+                PackageValue::new("mongoose", "1.0.0", "").with_range("^1.0.0"),
+            ],
+        );
+
+        assert_eq!(hash_map, result_hash_map);
+    }
+
+    #[test]
+    fn it_should_track_dependency_section() {
+        let json = r#"{
+          "dependencies": {
+            "mongoose": "^1.0.0"
+          },
+          "devDependencies": {
+            "mongoose": "^1.0.0",
+            "jest": "^29.0.0"
+          }
+        }"#;
+        let parsed: Value = serde_json::from_str(json).unwrap();
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        build_hash_map(parsed, Path::new("a"), &[], true, &mut hash_map);
+
+        assert_eq!(
+            hash_map["mongoose"],
+            vec![PackageValue::new("mongoose", "1.0.0", "a").with_range("^1.0.0")]
+        );
+        assert_eq!(
+            hash_map["jest"],
+            vec![PackageValue::new("jest", "29.0.0", "a")
+                .with_range("^29.0.0")
+                .with_section(Section::DevDependencies)]
+        );
+        assert!(!is_dev_only(&hash_map["mongoose"]));
+        assert!(is_dev_only(&hash_map["jest"]));
+    }
+
+    #[test]
+    fn it_should_return_struct_with_path() {
+        let json = r#"{
+          "dependencies": {
+            "mongoose": "^1.0.0"
+          }
+        }"#;
+        let parsed: Value = serde_json::from_str(json).unwrap();
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+
+        let path = Path::new("./src/data/package.json");
+        build_hash_map(parsed, path, &[], true, &mut hash_map);
+
+        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        result_hash_map.insert(
+            "mongoose".to_string(),
+            vec![PackageValue::new("mongoose", "1.0.0", path).with_range("^1.0.0")],
+        );
+
+        assert_eq!(hash_map, result_hash_map);
+    }
+
+    #[test]
+    fn it_should_sort_correctly() {
+        let json1 = r#"{
+          "dependencies": {
+            "mongoose": "^1.0.0"
+          }
+        }"#;
+        let json2 = r#"{
+          "devDependencies": {
+            "mongoose": "2.0.0"
+          }
+        }"#;
+
+        let json3 = r#"{
+          "devDependencies": {
+            "mongoose": "2.1.0"
+          }
+        }"#;
+        let json4 = r#"{
+          "devDependencies": {
+            "mongoose": "2.1.1"
+          }
+        }"#;
+        let json5 = r#"{
+          "devDependencies": {
+            "mongoose": "2.0.1"
+          }
+        }"#;
+        let parsed1: Value = serde_json::from_str(json1).unwrap();
+        let parsed2: Value = serde_json::from_str(json2).unwrap();
+        let parsed3: Value = serde_json::from_str(json3).unwrap();
+        let parsed4: Value = serde_json::from_str(json4).unwrap();
+        let parsed5: Value = serde_json::from_str(json5).unwrap();
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+
+        build_hash_map(parsed1, Path::new(""), &[], true, &mut hash_map);
+        build_hash_map(parsed2, Path::new(""), &[], true, &mut hash_map);
+        build_hash_map(parsed3, Path::new(""), &[], true, &mut hash_map);
+        build_hash_map(parsed4, Path::new(""), &[], true, &mut hash_map);
+        build_hash_map(parsed5, Path::new(""), &[], true, &mut hash_map);
+
+        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        result_hash_map.insert(
+            "mongoose".to_string(),
+            vec![
+                PackageValue::new("mongoose", "2.1.1", "").with_section(Section::DevDependencies),
+                PackageValue::new("mongoose", "2.1.0", "").with_section(Section::DevDependencies),
+                PackageValue::new("mongoose", "2.0.0", "").with_section(Section::DevDependencies),
+                PackageValue::new("mongoose", "1.0.0", "").with_range("^1.0.0"),
+                PackageValue::new("mongoose", "2.0.1", "").with_section(Section::DevDependencies),
+            ],
+        );
+
+        assert_eq!(hash_map, result_hash_map);
+    }
+
+    #[test]
+    fn it_should_skip_same_versions() {
+        let json1 = r#"{
+          "dependencies": {
+            "mongoose": "^1.0.0"
+          }
+        }"#;
+        let json2 = r#"{
+          "devDependencies": {
+            "mongoose": "1.0.0"
+          }
+        }"#;
+
+        let parsed1: Value = serde_json::from_str(json1).unwrap();
+        let parsed2: Value = serde_json::from_str(json2).unwrap();
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+
+        build_hash_map(parsed1, Path::new(""), &[], true, &mut hash_map);
+        build_hash_map(parsed2, Path::new(""), &[], true, &mut hash_map);
+
+        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        result_hash_map.insert(
+            "mongoose".to_string(),
+            vec![PackageValue::new("mongoose", "1.0.0", "").with_range("^1.0.0")],
+        );
+
+        assert_eq!(hash_map, result_hash_map);
+    }
+
+    #[test]
+    fn it_should_record_every_path_of_same_version() {
+        let json = r#"{
+          "dependencies": {
+            "mongoose": "^1.0.0"
+          }
+        }"#;
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+
+        for path in ["a", "b", "c", "b"] {
+            let parsed: Value = serde_json::from_str(json).unwrap();
+            build_hash_map(parsed, Path::new(path), &[], true, &mut hash_map);
+        }
+
+        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        result_hash_map.insert(
+            "mongoose".to_string(),
+            vec![
+                PackageValue::new("mongoose", "1.0.0", "a").with_range("^1.0.0"),
+                PackageValue::new("mongoose", "1.0.0", "b").with_range("^1.0.0"),
+                PackageValue::new("mongoose", "1.0.0", "c").with_range("^1.0.0"),
+            ],
+        );
+
+        assert_eq!(hash_map, result_hash_map);
+    }
+
+    #[test]
+    fn it_should_keep_same_version_paths_grouped() {
+        let json1 = r#"{
+          "dependencies": {
+            "mongoose": "^1.0.0"
+          }
+        }"#;
+        let json2 = r#"{
+          "dependencies": {
+            "mongoose": "^2.0.0"
+          }
+        }"#;
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+
+        build_hash_map(
+            serde_json::from_str(json1).unwrap(),
+            Path::new("a"),
+            &[],
+            true,
+            &mut hash_map,
+        );
+        build_hash_map(
+            serde_json::from_str(json2).unwrap(),
+            Path::new("b"),
+            &[],
+            true,
+            &mut hash_map,
+        );
+        build_hash_map(
+            serde_json::from_str(json1).unwrap(),
+            Path::new("c"),
+            &[],
+            true,
+            &mut hash_map,
+        );
+        build_hash_map(
+            serde_json::from_str(json2).unwrap(),
+            Path::new("d"),
+            &[],
+            true,
+            &mut hash_map,
+        );
+
+        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        result_hash_map.insert(
+            "mongoose".to_string(),
+            vec![
+                PackageValue::new("mongoose", "2.0.0", "b").with_range("^2.0.0"),
+                PackageValue::new("mongoose", "2.0.0", "d").with_range("^2.0.0"),
+                PackageValue::new("mongoose", "1.0.0", "a").with_range("^1.0.0"),
+                PackageValue::new("mongoose", "1.0.0", "c").with_range("^1.0.0"),
+            ],
+        );
+
+        assert_eq!(hash_map, result_hash_map);
+    }
+
+    #[test]
+    fn it_should_correctly_sort_versions() {
+        let json1 = r#"{
+          "dependencies": {
+            "mongoose": "^1.3.0"
+          }
+        }"#;
+        let json2 = r#"{
+          "devDependencies": {
+            "mongoose": "1.10.0"
+          }
+        }"#;
+
+        let parsed1: Value = serde_json::from_str(json1).unwrap();
+        let parsed2: Value = serde_json::from_str(json2).unwrap();
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+
+        build_hash_map(parsed1, Path::new(""), &[], true, &mut hash_map);
+        build_hash_map(parsed2, Path::new(""), &[], true, &mut hash_map);
+
+        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        result_hash_map.insert(
+            "mongoose".to_string(),
+            vec![
+                PackageValue::new("mongoose", "1.10.0", "").with_section(Section::DevDependencies),
+                PackageValue::new("mongoose", "1.3.0", "").with_range("^1.3.0"),
+            ],
+        );
+
+        assert_eq!(hash_map, result_hash_map);
+    }
+
+    #[test]
+    fn it_should_get_spread_between_endpoints() {
+        let values = vec![
+            PackageValue::new("test", "3.2.0", "./src/1"),
+            PackageValue::new("test", "2.5.1", "./src/2"),
+            PackageValue::new("test", "1.0.0", "./src/3"),
+        ];
+
+        assert_eq!(get_spread(&values), Spread::Major(2));
+        assert_eq!(get_spread(&values).to_string(), "2 major");
+    }
+
+    #[test]
+    fn it_should_get_spread_of_least_significant_difference() {
+        let minor = vec![
+            PackageValue::new("test", "1.4.0", "./src/1"),
+            PackageValue::new("test", "1.1.9", "./src/2"),
+        ];
+        let patch = vec![
+            PackageValue::new("test", "1.1.3", "./src/1"),
+            PackageValue::new("test", "1.1.0", "./src/2"),
+        ];
+
+        assert_eq!(get_spread(&minor), Spread::Minor(3));
+        assert_eq!(get_spread(&patch), Spread::Patch(3));
+    }
+
+    #[test]
+    fn it_should_group_names_differing_in_case() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/case_insensitive/a/package.json"),
+            PathBuf::from("./src/fixtures/case_insensitive/b/package.json"),
+        ];
+
+        let result =
+            find_case_insensitive_duplicates(paths.clone(), &[], &[], &mut Counters::default())
+                .duplicates;
+
+        let mut expected: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        expected.insert(
+            "react".to_string(),
+            vec![
+                PackageValue::new("react", "18.0.0", &paths[1]).with_range("^18.0.0"),
+                PackageValue::new("React", "17.0.0", &paths[0]).with_range("^17.0.0"),
+            ],
+        );
+        assert_eq!(result, expected);
+        assert!(
+            find_duplicate_dependencies(paths, &[], &[], &mut Counters::default())
+                .duplicates
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn it_should_split_groups_by_section() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/sections/a/package.json"),
+            PathBuf::from("./src/fixtures/sections/b/package.json"),
+            PathBuf::from("./src/fixtures/sections/c/package.json"),
+        ];
+        let dev = |version: &str, path: &PathBuf, package: &str| {
+            PackageValue::new("react", version, path)
+                .with_range(&format!("^{}", version))
+                .with_section(Section::DevDependencies)
+                .with_package(Some(package))
+        };
+
+        let merged = find_duplicate_dependencies(paths.clone(), &[], &[], &mut Counters::default())
+            .duplicates;
+        let split = find_duplicates_by_section(paths.clone(), &[], &[], &mut Counters::default())
+            .duplicates;
+
+        let mut expected: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        expected.insert(
+            "react".to_string(),
+            vec![
+                PackageValue::new("react", "18.0.0", &paths[0])
+                    .with_range("^18.0.0")
+                    .with_package(Some("a")),
+                dev("17.0.0", &paths[1], "b"),
+                dev("16.0.0", &paths[2], "c"),
+            ],
+        );
+        assert_eq!(merged, expected);
+
+        let mut expected: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        expected.insert(
+            "react (devDependencies)".to_string(),
+            vec![dev("17.0.0", &paths[1], "b"), dev("16.0.0", &paths[2], "c")],
+        );
+        assert_eq!(split, expected);
+    }
+
+    #[test]
+    fn it_should_apply_ignores_before_splitting_by_section() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/sections/b/package.json"),
+            PathBuf::from("./src/fixtures/sections/c/package.json"),
+        ];
+
+        let split = find_duplicates_by_section(
+            paths,
+            &["react".to_string()],
+            &[],
+            &mut Counters::default(),
+        )
+        .duplicates;
+
+        assert!(split.is_empty());
+    }
+
+    #[test]
+    fn it_should_find_duplicates_in_contents() {
+        let contents = vec![
+            (
+                PathBuf::from("a.tgz/package/package.json"),
+                "\u{feff}{\"dependencies\": {\"react\": \"^18.0.0\"}}".to_string(),
+            ),
+            (
+                PathBuf::from("a.tgz/package/node_modules/b/package.json"),
+                r#"{"dependencies": {"react": "^17.0.0", "lodash": "^4.0.0"}}"#.to_string(),
+            ),
+        ];
+        let mut counters = Counters::default();
+
+        let result = find_duplicates_in_contents(&contents, &[], &[], &mut counters).duplicates;
+
+        let mut expected: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        expected.insert(
+            "react".to_string(),
+            vec![
+                PackageValue::new("react", "18.0.0", &contents[0].0).with_range("^18.0.0"),
+                PackageValue::new("react", "17.0.0", &contents[1].0).with_range("^17.0.0"),
+            ],
+        );
+        assert_eq!(result, expected);
+        assert_eq!(counters.parsed, 2);
+    }
+
+    #[test]
+    fn it_should_list_single_version_dependencies_in_inventory() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/stats/a/package.json"),
+            PathBuf::from("./src/fixtures/stats/b/package.json"),
+            PathBuf::from("./src/fixtures/stats/c/package.json"),
+        ];
+        let dependencies = collect_dependencies(&paths, &[], &mut Counters::default());
+
+        let inventory = get_inventory(&dependencies, &["lodash".to_string()]);
+        let duplicates =
+            find_duplicate_dependencies(paths, &[], &[], &mut Counters::default()).duplicates;
+
+        let mut names: Vec<&String> = inventory.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["express", "react"]);
+        assert_eq!(inventory["react"].len(), 3);
+        assert!(!duplicates.contains_key("express"));
+    }
+
+    #[test]
+    fn it_should_recommend_highest_version() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/stats/a/package.json"),
+            PathBuf::from("./src/fixtures/stats/b/package.json"),
+            PathBuf::from("./src/fixtures/stats/c/package.json"),
+        ];
+        let duplicates =
+            find_duplicate_dependencies(paths, &[], &[], &mut Counters::default()).duplicates;
+
+        let recommendations = get_recommendations(&duplicates);
+
+        assert_eq!(recommendations.len(), 2);
+        assert_eq!(recommendations["lodash"], "4.0.0");
+        assert_eq!(recommendations["react"], "18.0.0");
+    }
+
+    #[test]
+    fn it_should_find_same_version_groups() {
+        let mut dependencies: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        for path in ["./a", "./b", "./c"] {
+            let json = r#"{ "dependencies": { "react": "^18.0.0" }, "devDependencies": { "react": "^18.0.0", "lodash": "^4.0.0" } }"#;
+            build_hash_map(
+                serde_json::from_str(json).unwrap(),
+                Path::new(path),
+                &[],
+                true,
+                &mut dependencies,
+            );
+        }
+        build_hash_map(
+            serde_json::from_str(r#"{ "dependencies": { "lodash": "^3.0.0", "vue": "^3.0.0" } }"#)
+                .unwrap(),
+            Path::new("./d"),
+            &[],
+            true,
+            &mut dependencies,
+        );
+
+        let groups = find_same_version_groups(&dependencies, &[]);
+
+        assert_eq!(
+            groups,
+            vec![SameVersionGroup {
+                name: "react".to_string(),
+                range: "^18.0.0".to_string(),
+                files: 3,
+            }]
+        );
+        assert!(find_same_version_groups(&dependencies, &["react".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn it_should_find_workspace_referenced_from_registry() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/workspace_protocol/lib/package.json"),
+            PathBuf::from("./src/fixtures/workspace_protocol/app/package.json"),
+            PathBuf::from("./src/fixtures/workspace_protocol/web/package.json"),
+        ];
+        let dependencies = collect_dependencies(&paths, &[], &mut Counters::default());
+        let package_names = collect_package_names(&paths);
+
+        let mixes = find_workspace_protocol_mixes(&dependencies, &package_names);
+
+        assert_eq!(package_names.len(), 3);
+        assert_eq!(mixes.keys().collect::<Vec<&String>>(), vec!["@acme/lib"]);
+        let ranges: Vec<&str> = mixes["@acme/lib"]
+            .iter()
+            .map(|v| v.range.as_str())
+            .collect();
+        assert!(ranges.contains(&"workspace:*"));
+        assert!(ranges.contains(&"^1.0.0"));
+    }
+
+    #[test]
+    fn it_should_find_case_conflicts() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/case_insensitive/a/package.json"),
+            PathBuf::from("./src/fixtures/case_insensitive/b/package.json"),
+        ];
+        let dependencies = collect_dependencies(&paths, &[], &mut Counters::default());
+
+        let conflicts = find_case_conflicts(&dependencies);
+
+        let mut expected: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        expected.insert(
+            "react".to_string(),
+            vec!["React".to_string(), "react".to_string()],
+        );
+        assert_eq!(conflicts, expected);
+    }
+
+    #[test]
+    fn it_should_find_case_collisions_in_the_same_file() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/case_in_file/package.json"),
+            PathBuf::from("./src/fixtures/case_insensitive/a/package.json"),
+            PathBuf::from("./src/fixtures/case_insensitive/b/package.json"),
+        ];
+        let dependencies = collect_dependencies(&paths, &[], &mut Counters::default());
+
+        let collisions = find_case_collisions(&dependencies);
+
+        assert_eq!(
+            collisions,
+            vec![
+                CaseCollision {
+                    path: paths[0].clone(),
+                    names: vec!["Lodash".to_string(), "lodash".to_string()],
+                },
+                CaseCollision {
+                    path: paths[0].clone(),
+                    names: vec!["React".to_string(), "react".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_group_versions_by_granularity() {
+        let duplicates = || {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            for (name, versions) in [
+                ("a", ["1.2.0", "1.3.0"]),
+                ("b", ["1.2.0", "1.2.5"]),
+                ("c", ["1.0.0", "2.0.0"]),
+            ] {
+                let values = versions
+                    .iter()
+                    .map(|version| PackageValue::new(name, version, "./a"))
+                    .collect();
+                hash_map.insert(name.to_string(), values);
+            }
+            hash_map
+        };
+        let names = |granularity| {
+            let mut hash_map = duplicates();
+            retain_by_granularity(&mut hash_map, granularity);
+            let mut names: Vec<String> = hash_map.into_keys().collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(Granularity::Patch), vec!["a", "b", "c"]);
+        assert_eq!(names(Granularity::Minor), vec!["a", "c"]);
+        assert_eq!(names(Granularity::Major), vec!["c"]);
+    }
+
+    #[test]
+    fn it_should_retain_groups_declared_in_enough_files() {
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        hash_map.insert(
+            "a".to_string(),
+            vec![
+                PackageValue::new("a", "1.0.0", "./a"),
+                PackageValue::new("a", "2.0.0", "./b"),
+            ],
+        );
+        hash_map.insert(
+            "b".to_string(),
+            vec![
+                PackageValue::new("b", "1.0.0", "./a"),
+                PackageValue::new("b", "2.0.0", "./b"),
+                PackageValue::new("b", "2.0.0", "./c"),
+            ],
+        );
+
+        retain_by_min_files(&mut hash_map, 3);
+
+        assert_eq!(hash_map.into_keys().collect::<Vec<String>>(), vec!["b"]);
+    }
+
+    #[test]
+    fn it_should_keep_same_version_occurrences_without_dedupe() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/same_version/a/package.json"),
+            PathBuf::from("./src/fixtures/same_version/b/package.json"),
+        ];
+
+        let deduped =
+            find_duplicate_dependencies(paths.clone(), &[], &[], &mut Counters::default())
+                .duplicates;
+        let every = find_every_occurrence(paths, &[], &[], &mut Counters::default()).duplicates;
+
+        assert_eq!(deduped["react"].len(), 2);
+        assert_eq!(
+            every["react"]
+                .iter()
+                .map(|v| (v.range.as_str(), v.section))
+                .collect::<Vec<(&str, Section)>>(),
+            vec![
+                ("^18.0.0", Section::Dependencies),
+                ("^18.0.0", Section::DevDependencies),
+                ("^17.0.0", Section::Dependencies),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_stop_at_first_duplicate() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/stats/a/package.json"),
+            PathBuf::from("./src/fixtures/stats/b/package.json"),
+            PathBuf::from("./src/fixtures/stats/c/package.json"),
+        ];
+        let mut counters = Counters::default();
+
+        let duplicate = find_first_duplicate(&paths, &[], &[], &mut counters, |_, _| true);
+
+        assert_eq!(duplicate.map(|(_, path)| path), Some(paths[1].clone()));
+        assert_eq!(counters.parsed, 2);
+    }
+
+    #[test]
+    fn it_should_read_every_file_without_duplicates() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/stats/a/package.json"),
+            PathBuf::from("./src/fixtures/stats/b/package.json"),
+        ];
+        let ignores = vec!["lodash".to_string(), "react".to_string()];
+        let mut counters = Counters::default();
+
+        let duplicate = find_first_duplicate(&paths, &ignores, &[], &mut counters, |_, _| true);
+
+        assert_eq!(duplicate, None);
+        assert_eq!(counters.parsed, 2);
+    }
+
+    #[test]
+    fn it_should_drop_prerelease_versions() {
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        let path = Path::new("./a");
+        for (name, range) in [
+            ("canary", "^1.0.0"),
+            ("canary", "1.1.0-next.5"),
+            ("mixed", "^1.0.0"),
+            ("mixed", "^2.0.0-beta.1"),
+            ("mixed", "~3.0.0"),
+            ("stable", "1.0.0"),
+            ("stable", "2.0.0"),
+        ] {
+            process_dependency(name, range, &mut hash_map, path, Section::Dependencies);
+        }
+
+        drop_prereleases(&mut hash_map);
+
+        let mut names: Vec<&String> = hash_map.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["mixed", "stable"]);
+        let ranges: Vec<&str> = hash_map["mixed"].iter().map(|v| v.range.as_str()).collect();
+        assert_eq!(ranges, vec!["~3.0.0", "^1.0.0"]);
+    }
+
+    #[test]
+    fn it_should_detect_prerelease_ranges() {
+        assert!(is_prerelease("1.0.0-next.5"));
+        assert!(is_prerelease("^v2.0.0-rc.1"));
+        assert!(is_prerelease(">=1.0.0-alpha <2.0.0"));
+        assert!(!is_prerelease("^1.0.0"));
+        assert!(!is_prerelease("1.x"));
+    }
+
+    #[test]
+    fn it_should_get_scope() {
+        assert_eq!(get_scope("@acme/ui"), Some("@acme"));
+        assert_eq!(get_scope("lodash"), None);
+        assert_eq!(get_scope("@broken"), None);
+    }
+
+    #[test]
+    fn it_should_order_large_date_based_versions() {
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        for (version, path) in [
+            ("20230101.0.0", "a"),
+            ("20240101.0.0", "b"),
+            ("99999999999.0.0", "c"),
+            ("123456789012345678901.0.0", "d"),
+            ("99999999999999999999999.0.0", "e"),
+        ] {
+            process_dependency(
+                "tzdata",
+                version,
+                &mut hash_map,
+                Path::new(path),
+                Section::Dependencies,
+            );
+        }
+
+        let versions: Vec<&str> = hash_map["tzdata"]
+            .iter()
+            .map(|v| v.version.as_str())
+            .collect();
+        assert_eq!(
+            versions,
+            vec![
+                "99999999999999999999999.0.0",
+                "123456789012345678901.0.0",
+                "99999999999.0.0",
+                "20240101.0.0",
+                "20230101.0.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_compare_versions_overflowing_u64_as_strings() {
+        assert_eq!(get_versions("99999999999.1.2"), (99999999999, 1, 2));
+        assert_eq!(get_versions("99999999999999999999999"), (u64::MAX, 0, 0));
+        assert_eq!(
+            compare_versions("99999999999999999999999", "99999999999999999999998"),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_versions("0099999999999999999999", "99999999999999999999"),
+            Ordering::Equal
+        );
+        assert_eq!(compare_versions("1", "1.0.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.10.0", "1.9.0"), Ordering::Greater);
+    }
+
+    #[test]
+    fn it_should_classify_non_registry_specifiers() {
+        for specifier in [
+            "git+https://github.com/x/y#v1.0.0",
+            "github:org/repo",
+            "file:../local-lib",
+            "org/repo#main",
+            "https://example.com/lib.tgz",
+        ] {
+            assert_eq!(
+                get_version_kind(specifier),
+                VersionKind::NonRegistry,
+                "{}",
+                specifier
+            );
+        }
+        for specifier in ["^1.0.0", ">= 1.0.0", "latest", "npm:@acme/lib@^1.0.0"] {
+            assert_eq!(
+                get_version_kind(specifier),
+                VersionKind::Registry,
+                "{}",
+                specifier
+            );
+        }
+        for specifier in [">=1.0.0 <2.0.0", "1.0.0 || 2.0.0", "1.0.0 - 2.0.0"] {
+            assert_eq!(
+                get_version_kind(specifier),
+                VersionKind::Range,
+                "{}",
+                specifier
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_read_custom_fields() {
+        let parsed: Value = serde_json::from_str(
+            r#"{
+                "dependencies": {"react": "^18.0.0"},
+                "acme:deps": {"lodash": "^4.17.0"},
+                "config": {"tools": {"eslint": "^8.0.0"}},
+                "peerDependenciesMeta": {"react": {"optional": true}}
+            }"#,
+        )
+        .unwrap();
+        let fields = [
+            "/acme:deps".to_string(),
+            "/config/tools".to_string(),
+            "/peerDependenciesMeta".to_string(),
+            "/missing".to_string(),
+        ];
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
 
-        let expected = "testignore\ntestignore2\ntestignore3";
+        build_hash_map(parsed, Path::new("a"), &fields, true, &mut hash_map);
 
-        assert_eq!(actual, expected);
+        let mut names: Vec<&String> = hash_map.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["eslint", "lodash", "react"]);
+        assert_eq!(hash_map["lodash"][0].version, "4.17.0");
+        assert_eq!(hash_map["react"].len(), 1);
     }
 
     #[test]
-    fn it_should_build_hash_map() {
-        let json = r#"{
-          "dependencies": {
-            "mongoose": "^1.0.0"
-          }
-        }"#;
-        let parsed: Value = serde_json::from_str(json).unwrap();
+    fn it_should_keep_compound_ranges_verbatim() {
         let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
-        build_hash_map(parsed, "", &mut hash_map);
+        let path = Path::new("./a");
+        process_dependency(
+            "lib",
+            ">=1.0.0 <2.0.0",
+            &mut hash_map,
+            path,
+            Section::Dependencies,
+        );
+        process_dependency(
+            "lib",
+            "1.0.0 || 2.0.0",
+            &mut hash_map,
+            path,
+            Section::Dependencies,
+        );
+        process_dependency("lib", "^3.0.0", &mut hash_map, path, Section::Dependencies);
 
-        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
-        result_hash_map.insert(
-            "mongoose".to_string(),
-            vec![PackageValue::new("mongoose", "1.0.0", "")],
+        let versions: Vec<&str> = hash_map["lib"].iter().map(|v| v.version.as_str()).collect();
+        assert_eq!(versions, vec!["3.0.0", ">=1.0.0 <2.0.0", "1.0.0 || 2.0.0"]);
+        assert_eq!(hash_map["lib"][1].kind, VersionKind::Range);
+        assert_eq!(get_spread(&hash_map["lib"]), Spread::Major(2));
+    }
+
+    #[test]
+    fn it_should_order_compound_ranges_by_first_comparator() {
+        assert_eq!(get_versions(">=1.2.3 <2.0.0"), (1, 2, 3));
+        assert_eq!(get_versions("1.0.0 || 2.0.0"), (1, 0, 0));
+        assert_eq!(
+            compare_versions(">=1.0.0 <2.0.0", "0.9.0"),
+            Ordering::Greater
         );
-        assert_eq!(hash_map, result_hash_map);
     }
 
     #[test]
-    fn it_should_add_two_values() {
-        let json1 = r#"{
-          "dependencies": {
-            "mongoose": "^1.0.0"
-          }
-        }"#;
-        let json2 = r#"{
-          "dependencies": {
-            "mongoose": "2.0.0"
-          }
-        }"#;
-        let parsed1: Value = serde_json::from_str(json1).unwrap();
-        let parsed2: Value = serde_json::from_str(json2).unwrap();
+    fn it_should_keep_non_registry_specifiers_out_of_duplicates() {
         let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
-        build_hash_map(parsed1, "", &mut hash_map);
-        build_hash_map(parsed2, "", &mut hash_map);
+        for (specifier, path) in [
+            ("^1.0.0", "a"),
+            ("git+https://github.com/x/lib#v2.0.0", "b"),
+            ("github:x/lib", "c"),
+            ("file:../lib", "d"),
+        ] {
+            process_dependency(
+                "lib",
+                specifier,
+                &mut hash_map,
+                Path::new(path),
+                Section::Dependencies,
+            );
+        }
 
-        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
-        result_hash_map.insert(
-            "mongoose".to_string(),
+        let non_registry = find_non_registry_dependencies(&hash_map);
+        keep_bad_values(&mut hash_map, &[]);
+
+        assert!(hash_map.is_empty());
+        assert_eq!(
+            non_registry,
             vec![
-                PackageValue::new("mongoose", "2.0.0", ""),
-                PackageValue::new("mongoose", "1.0.0", ""),
-            ],
+                PackageValue::new("lib", "git+https://github.com/x/lib#v2.0.0", "b")
+                    .with_kind(VersionKind::NonRegistry),
+                PackageValue::new("lib", "github:x/lib", "c").with_kind(VersionKind::NonRegistry),
+                PackageValue::new("lib", "file:../lib", "d").with_kind(VersionKind::NonRegistry),
+            ]
         );
-
-        assert_eq!(hash_map, result_hash_map);
     }
 
     #[test]
-    fn it_should_call_all_together() {
-        let path = "./src/data/package.json".to_string();
-        let result = find_duplicate_dependencies(vec![path], &[]);
+    fn it_should_normalize_v_prefixed_versions() {
+        assert_eq!(normalize_version("v1.2.3"), "1.2.3");
+        assert_eq!(normalize_version("V1.2.3"), "1.2.3");
+        assert_eq!(normalize_version(">=v1.0.0"), ">=1.0.0");
+        assert_eq!(normalize_version(">=v1.0.0 <V2.0.0"), ">=1.0.0 <2.0.0");
+        assert_eq!(normalize_version("^v1 || ~v2.1"), "^1 || ~2.1");
+        assert_eq!(normalize_version("1.0.0-rev2"), "1.0.0-rev2");
+        assert_eq!(normalize_version("latest"), "latest");
 
-        assert_eq!(result, HashMap::new());
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        process_dependency(
+            "lib",
+            "v1.2.3",
+            &mut hash_map,
+            Path::new("a"),
+            Section::Dependencies,
+        );
+        process_dependency(
+            "lib",
+            ">=v1.0.0",
+            &mut hash_map,
+            Path::new("b"),
+            Section::Dependencies,
+        );
+
+        assert_eq!(
+            hash_map["lib"],
+            vec![
+                PackageValue::new("lib", "1.2.3", "a").with_range("v1.2.3"),
+                PackageValue::new("lib", "1.0.0", "b").with_range(">=v1.0.0"),
+            ]
+        );
     }
 
     #[test]
-    fn it_should_also_parse_dev_dependencies() {
-        let json1 = r#"{
-          "dependencies": {
-            "mongoose": "^1.0.0"
-          }
-        }"#;
-        let json2 = r#"{
-          "devDependencies": {
-            "mongoose": "2.0.0"
-          }
-        }"#;
-        let parsed1: Value = serde_json::from_str(json1).unwrap();
-        let parsed2: Value = serde_json::from_str(json2).unwrap();
+    fn it_should_keep_versions_of_different_specificity_apart() {
         let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
-        build_hash_map(parsed1, "", &mut hash_map);
-        build_hash_map(parsed2, "", &mut hash_map);
+        for (version, path) in [("1.0.0", "a"), ("^1.0", "b"), ("1", "c"), ("~1.0.0", "d")] {
+            process_dependency(
+                "mongoose",
+                version,
+                &mut hash_map,
+                Path::new(path),
+                Section::Dependencies,
+            );
+        }
 
         let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
         result_hash_map.insert(
             "mongoose".to_string(),
             vec![
-                PackageValue::new("mongoose", "2.0.0", ""),
-                PackageValue::new("mongoose", "1.0.0", ""),
+                PackageValue::new("mongoose", "1", "c"),
+                PackageValue::new("mongoose", "1.0", "b").with_range("^1.0"),
+                PackageValue::new("mongoose", "1.0.0", "a"),
+                PackageValue::new("mongoose", "1.0.0", "d").with_range("~1.0.0"),
             ],
         );
 
         assert_eq!(hash_map, result_hash_map);
+        assert_eq!(count_versions(&hash_map["mongoose"]), 3);
     }
 
     #[test]
-    fn it_should_return_struct_with_path() {
-        let json = r#"{
-          "dependencies": {
-            "mongoose": "^1.0.0"
-          }
-        }"#;
-        let parsed: Value = serde_json::from_str(json).unwrap();
-        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+    fn it_should_track_specificity() {
+        assert_eq!(PackageValue::new("a", "1", "").specificity, 1);
+        assert_eq!(PackageValue::new("a", "1.0", "").specificity, 2);
+        assert_eq!(PackageValue::new("a", "1.0.0", "").specificity, 3);
+        assert_eq!(PackageValue::new("a", "", "").specificity, 0);
+    }
 
-        let path = "./src/data/package.json";
-        build_hash_map(parsed, path, &mut hash_map);
+    fn patterns(entries: Vec<IgnoreEntry>) -> Vec<String> {
+        entries.into_iter().map(|entry| entry.pattern).collect()
+    }
 
-        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
-        result_hash_map.insert(
-            "mongoose".to_string(),
-            vec![PackageValue::new("mongoose", "1.0.0", path)],
-        );
+    #[test]
+    fn it_should_parse_ignore() {
+        let parsed = patterns(parse_ignores("mongoose\nexpress\n"));
+        assert_eq!(parsed, vec!["mongoose", "express"]);
+    }
 
-        assert_eq!(hash_map, result_hash_map);
+    #[test]
+    fn it_should_apply_custom_ignore_file() {
+        let ignores = get_ignore_values("./src/fixtures/custom_ignore/config/nodedup.ignore");
+        let paths = vec![
+            PathBuf::from("./src/fixtures/stats/a/package.json"),
+            PathBuf::from("./src/fixtures/stats/b/package.json"),
+        ];
+
+        let result =
+            find_duplicate_dependencies(paths, &ignores, &[], &mut Counters::default()).duplicates;
+
+        assert_eq!(result.len(), 1);
+        assert!(result.contains_key("lodash"));
     }
 
     #[test]
-    fn it_should_sort_correctly() {
-        let json1 = r#"{
-          "dependencies": {
-            "mongoose": "^1.0.0"
-          }
-        }"#;
-        let json2 = r#"{
-          "devDependencies": {
-            "mongoose": "2.0.0"
-          }
-        }"#;
+    fn it_should_combine_cli_and_file_ignores() {
+        let file_ignores = get_ignore_values("./src/fixtures/custom_ignore/config/nodedup.ignore");
+        let cli_ignores = vec!["lodash".to_string()];
+        let paths = vec![
+            PathBuf::from("./src/fixtures/stats/a/package.json"),
+            PathBuf::from("./src/fixtures/stats/b/package.json"),
+        ];
+
+        let combined = merge_ignores(file_ignores.clone(), &cli_ignores, false);
+        let cli_only = merge_ignores(file_ignores, &cli_ignores, true);
+
+        assert!(find_duplicate_dependencies(
+            paths.clone(),
+            &combined,
+            &[],
+            &mut Counters::default()
+        )
+        .duplicates
+        .is_empty());
+        let result =
+            find_duplicate_dependencies(paths, &cli_only, &[], &mut Counters::default()).duplicates;
+        assert_eq!(result.keys().collect::<Vec<&String>>(), vec!["react"]);
+    }
 
-        let json3 = r#"{
-          "devDependencies": {
-            "mongoose": "2.1.0"
-          }
-        }"#;
-        let json4 = r#"{
-          "devDependencies": {
-            "mongoose": "2.1.1"
-          }
-        }"#;
-        let json5 = r#"{
-          "devDependencies": {
-            "mongoose": "2.0.1"
-          }
-        }"#;
-        let parsed1: Value = serde_json::from_str(json1).unwrap();
-        let parsed2: Value = serde_json::from_str(json2).unwrap();
-        let parsed3: Value = serde_json::from_str(json3).unwrap();
-        let parsed4: Value = serde_json::from_str(json4).unwrap();
-        let parsed5: Value = serde_json::from_str(json5).unwrap();
+    #[test]
+    fn it_should_ignore_names_matching_glob() {
         let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        for name in ["@types/node", "@types/react", "react"] {
+            hash_map.insert(
+                name.to_string(),
+                vec![
+                    PackageValue::new(name, "2.0.0", "./src/1"),
+                    PackageValue::new(name, "1.0.0", "./src/2"),
+                ],
+            );
+        }
 
-        build_hash_map(parsed1, "", &mut hash_map);
-        build_hash_map(parsed2, "", &mut hash_map);
-        build_hash_map(parsed3, "", &mut hash_map);
-        build_hash_map(parsed4, "", &mut hash_map);
-        build_hash_map(parsed5, "", &mut hash_map);
-
-        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
-        result_hash_map.insert(
-            "mongoose".to_string(),
-            vec![
-                PackageValue::new("mongoose", "2.1.1", ""),
-                PackageValue::new("mongoose", "2.1.0", ""),
-                PackageValue::new("mongoose", "2.0.0", ""),
-                PackageValue::new("mongoose", "1.0.0", ""),
-                PackageValue::new("mongoose", "2.0.1", ""),
-            ],
-        );
+        keep_bad_values(&mut hash_map, &["@types/*".to_string()]);
 
-        assert_eq!(hash_map, result_hash_map);
+        assert_eq!(hash_map.keys().collect::<Vec<_>>(), vec!["react"]);
     }
 
     #[test]
-    fn it_should_skip_same_versions() {
-        let json1 = r#"{
-          "dependencies": {
-            "mongoose": "^1.0.0"
-          }
-        }"#;
-        let json2 = r#"{
-          "devDependencies": {
-            "mongoose": "1.0.0"
-          }
-        }"#;
-
-        let parsed1: Value = serde_json::from_str(json1).unwrap();
-        let parsed2: Value = serde_json::from_str(json2).unwrap();
+    fn it_should_exclude_scopes() {
         let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        for name in ["@internal/api", "@internal/ui", "@acme/ui", "lodash"] {
+            hash_map.insert(
+                name.to_string(),
+                vec![
+                    PackageValue::new(name, "2.0.0", "./src/1"),
+                    PackageValue::new(name, "1.0.0", "./src/2"),
+                ],
+            );
+        }
 
-        build_hash_map(parsed1, "", &mut hash_map);
-        build_hash_map(parsed2, "", &mut hash_map);
+        keep_bad_values(
+            &mut hash_map,
+            &get_scope_ignores(&["@internal".to_string()]),
+        );
 
-        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
-        result_hash_map.insert(
-            "mongoose".to_string(),
-            vec![PackageValue::new("mongoose", "1.0.0", "")],
+        let mut names: Vec<&String> = hash_map.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["@acme/ui", "lodash"]);
+    }
+
+    #[test]
+    fn it_should_accept_scopes_without_at_sign() {
+        assert_eq!(
+            get_scope_ignores(&["@internal".to_string(), "acme".to_string()]),
+            vec!["@internal/*".to_string(), "@acme/*".to_string()]
         );
+    }
 
-        assert_eq!(hash_map, result_hash_map);
+    #[test]
+    fn it_should_match_ignores_exactly_or_by_glob() {
+        let ignores = vec!["lodash".to_string(), "@babel/*".to_string()];
+
+        assert!(is_ignored("lodash", &ignores));
+        assert!(is_ignored("@babel/core", &ignores));
+        assert!(!is_ignored("lodash.merge", &ignores));
+        assert!(!is_ignored("@types/babel__core", &ignores));
     }
 
     #[test]
-    fn it_should_correctly_sort_versions() {
-        let json1 = r#"{
-          "dependencies": {
-            "mongoose": "^1.3.0"
-          }
-        }"#;
-        let json2 = r#"{
-          "devDependencies": {
-            "mongoose": "1.10.0"
-          }
-        }"#;
+    fn it_should_skip_comment_lines() {
+        let parsed = patterns(parse_ignores(
+            "# managed by the platform team\nmongoose\n  # indented\n",
+        ));
+        assert_eq!(parsed, vec!["mongoose"]);
+    }
 
-        let parsed1: Value = serde_json::from_str(json1).unwrap();
-        let parsed2: Value = serde_json::from_str(json2).unwrap();
-        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+    #[test]
+    fn it_should_skip_blank_lines() {
+        let parsed = patterns(parse_ignores("mongoose\n\n   \nexpress"));
+        assert_eq!(parsed, vec!["mongoose", "express"]);
+    }
 
-        build_hash_map(parsed1, "", &mut hash_map);
-        build_hash_map(parsed2, "", &mut hash_map);
+    #[test]
+    fn it_should_strip_inline_comments() {
+        let parsed = patterns(parse_ignores(
+            "mongoose # pinned by legacy app\nexpress#no space",
+        ));
+        assert_eq!(parsed, vec!["mongoose", "express"]);
+    }
 
-        let mut result_hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
-        result_hash_map.insert(
-            "mongoose".to_string(),
+    #[test]
+    fn it_should_keep_inline_comments_as_reasons() {
+        let parsed = parse_ignores("lodash # migrating in Q3\nexpress\nreact #\n");
+        assert_eq!(
+            parsed,
             vec![
-                PackageValue::new("mongoose", "1.10.0", ""),
-                PackageValue::new("mongoose", "1.3.0", ""),
-            ],
+                IgnoreEntry {
+                    pattern: "lodash".to_string(),
+                    reason: Some("migrating in Q3".to_string()),
+                },
+                IgnoreEntry {
+                    pattern: "express".to_string(),
+                    reason: None,
+                },
+                IgnoreEntry {
+                    pattern: "react".to_string(),
+                    reason: None,
+                },
+            ]
         );
+    }
 
-        assert_eq!(hash_map, result_hash_map);
+    #[test]
+    fn it_should_parse_crlf_ignores() {
+        let parsed = parse_ignores("mongoose \r\nexpress\t\r\nlodash # pinned\r\n");
+        assert_eq!(
+            parsed,
+            vec![
+                IgnoreEntry {
+                    pattern: "mongoose".to_string(),
+                    reason: None,
+                },
+                IgnoreEntry {
+                    pattern: "express".to_string(),
+                    reason: None,
+                },
+                IgnoreEntry {
+                    pattern: "lodash".to_string(),
+                    reason: Some("pinned".to_string()),
+                },
+            ]
+        );
     }
 
     #[test]
-    fn it_should_parse_ignore() {
-        let parsed = parse_ignores("mongoose\nexpress\n");
+    fn it_should_parse_cr_only_ignores() {
+        let parsed = patterns(parse_ignores("mongoose\rexpress"));
         assert_eq!(parsed, vec!["mongoose", "express"]);
     }
 
+    #[test]
+    fn it_should_skip_trailing_blank_lines() {
+        let parsed = patterns(parse_ignores("mongoose\n\n\r\n  \r\n\n"));
+        assert_eq!(parsed, vec!["mongoose"]);
+    }
+
     #[test]
     fn it_should_return_empty_ignore() {
         let parsed = parse_ignores("");
         assert!(parsed.is_empty());
     }
 
+    mod duplicate_keys {
+        use super::*;
+
+        #[test]
+        fn it_should_detect_duplicate_dependency_key() {
+            let result =
+                find_duplicate_keys(&[PathBuf::from("./src/fixtures/duplicate_keys/package.json")]);
+
+            assert_eq!(
+                result,
+                vec![DuplicateKey {
+                    name: "react".to_string(),
+                    section: "dependencies".to_string(),
+                    path: PathBuf::from("./src/fixtures/duplicate_keys/package.json"),
+                }]
+            );
+        }
+
+        #[test]
+        fn it_should_not_report_same_key_in_different_sections() {
+            let json = r#"{
+              "dependencies": {
+                "react": "1.0.0"
+              },
+              "devDependencies": {
+                "react": "2.0.0"
+              }
+            }"#;
+
+            assert!(get_duplicate_keys(json, Path::new("")).is_empty());
+        }
+
+        #[test]
+        fn it_should_ignore_duplicates_outside_dependencies() {
+            let json = r#"{
+              "name": "a",
+              "name": "b",
+              "scripts": {
+                "test": "a",
+                "test": "b"
+              }
+            }"#;
+
+            assert!(get_duplicate_keys(json, Path::new("")).is_empty());
+        }
+    }
+
+    mod missing {
+        use super::*;
+
+        #[test]
+        fn it_should_report_files_missing_dependency() {
+            let paths = [
+                PathBuf::from("./src/fixtures/stats/a/package.json"),
+                PathBuf::from("./src/fixtures/stats/b/package.json"),
+                PathBuf::from("./src/fixtures/stats/c/package.json"),
+            ];
+            let dependencies = collect_dependencies(&paths, &[], &mut Counters::default());
+
+            let missing = find_missing_dependencies(
+                &paths,
+                &dependencies,
+                &["lodash".to_string(), "react".to_string(), "vue".to_string()],
+            );
+
+            let mut expected: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+            expected.insert("lodash".to_string(), vec![paths[2].clone()]);
+            expected.insert("react".to_string(), vec![]);
+            expected.insert("vue".to_string(), paths.to_vec());
+            assert_eq!(missing, expected);
+        }
+    }
+
+    mod bundled {
+        use super::*;
+
+        fn scan(files: &[(&str, &str)]) -> Vec<BundledMismatch> {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            let mut bundled: Vec<BundledMismatch> = Vec::new();
+            for (path, json) in files {
+                let value: Value = serde_json::from_str(json).unwrap();
+                bundled.extend(collect_bundled(&value, Path::new(path)));
+                build_hash_map(value, Path::new(path), &[], true, &mut hash_map);
+            }
+
+            cross_reference_bundled(bundled, &hash_map)
+        }
+
+        #[test]
+        fn it_should_report_bundled_version_mismatch() {
+            let bundler = r#"{
+              "dependencies": {
+                "lodash": "^4.0.0"
+              },
+              "bundledDependencies": ["lodash"]
+            }"#;
+            let other = r#"{
+              "dependencies": {
+                "lodash": "3.0.0"
+              }
+            }"#;
+
+            let result = scan(&[("a", bundler), ("b", other)]);
+
+            assert_eq!(
+                result,
+                vec![BundledMismatch {
+                    name: "lodash".to_string(),
+                    path: PathBuf::from("a"),
+                    version: Some("4.0.0".to_string()),
+                    elsewhere: vec![PackageValue::new("lodash", "3.0.0", "b")],
+                }]
+            );
+        }
+
+        #[test]
+        fn it_should_skip_matching_bundled_ranges() {
+            let bundler = r#"{
+              "dependencies": {
+                "lodash": ">=1.0.0 <2.0.0"
+              },
+              "bundledDependencies": ["lodash"]
+            }"#;
+            let same = r#"{
+              "dependencies": {
+                "lodash": ">=1.0.0 <2.0.0"
+              }
+            }"#;
+            let other = r#"{
+              "dependencies": {
+                "lodash": ">=1.0.0 <3.0.0"
+              }
+            }"#;
+
+            assert!(scan(&[("a", bundler), ("b", same)]).is_empty());
+
+            let result = scan(&[("a", bundler), ("b", other)]);
+            assert_eq!(result.len(), 1);
+            assert_eq!(result[0].version.as_deref(), Some(">=1.0.0 <2.0.0"));
+        }
+
+        #[test]
+        fn it_should_support_bundle_dependencies_spelling() {
+            let bundler = r#"{
+              "bundleDependencies": ["lodash"]
+            }"#;
+            let other = r#"{
+              "devDependencies": {
+                "lodash": "3.0.0"
+              }
+            }"#;
+
+            let result = scan(&[("a", bundler), ("b", other)]);
+
+            assert_eq!(
+                result,
+                vec![BundledMismatch {
+                    name: "lodash".to_string(),
+                    path: PathBuf::from("a"),
+                    version: None,
+                    elsewhere: vec![PackageValue::new("lodash", "3.0.0", "b")
+                        .with_section(Section::DevDependencies)],
+                }]
+            );
+        }
+
+        #[test]
+        fn it_should_skip_matching_bundled_versions() {
+            let bundler = r#"{
+              "dependencies": {
+                "lodash": "^4.0.0",
+                "react": "^18.0.0"
+              },
+              "bundledDependencies": ["lodash", "react"]
+            }"#;
+            let other = r#"{
+              "dependencies": {
+                "lodash": "4.0.0"
+              }
+            }"#;
+
+            let result = scan(&[("a", bundler), ("b", other)]);
+
+            assert!(result.is_empty());
+        }
+    }
+
     mod keep_bad_values {
         use super::*;
 
@@ -479,6 +2837,22 @@ mod tests {
             assert_eq!(hash_map, HashMap::new());
         }
 
+        #[test]
+        fn it_should_remove_same_version_in_many_paths() {
+            let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+            hash_map.insert(
+                "mongoose".to_string(),
+                vec![
+                    PackageValue::new("mongoose", "1.0.0", "a"),
+                    PackageValue::new("mongoose", "1.0.0", "b"),
+                ],
+            );
+
+            keep_bad_values(&mut hash_map, &[]);
+
+            assert_eq!(hash_map, HashMap::new());
+        }
+
         #[test]
         fn it_should_return_empty_vec_for_good_values() {
             let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
@@ -0,0 +1,276 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+use walkdir::{DirEntry, WalkDir};
+
+use crate::parser::PackageValue;
+
+const SOURCE_EXTENSIONS: [&str; 6] = ["js", "jsx", "mjs", "cjs", "ts", "tsx"];
+// As listed by `require('module').builtinModules`
+const BUILTIN_MODULES: [&str; 56] = [
+    "_http_agent",
+    "_http_client",
+    "_http_common",
+    "_http_incoming",
+    "_http_outgoing",
+    "_http_server",
+    "_stream_duplex",
+    "_stream_passthrough",
+    "_stream_readable",
+    "_stream_transform",
+    "_stream_wrap",
+    "_stream_writable",
+    "_tls_common",
+    "_tls_wrap",
+    "assert",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "domain",
+    "events",
+    "fs",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "module",
+    "net",
+    "os",
+    "path",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "repl",
+    "stream",
+    "string_decoder",
+    "sys",
+    "timers",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PhantomDependency {
+    pub name: String,
+    pub path: PathBuf,
+    pub source: PathBuf,
+}
+
+// A package imported by a workspace without being declared in it, while a sibling declares it,
+// only works as long as the package manager keeps hoisting it
+pub fn find_phantom_dependencies(
+    paths: &[PathBuf],
+    dependencies: &HashMap<String, Vec<PackageValue>>,
+) -> Vec<PhantomDependency> {
+    let pattern = import_pattern();
+
+    let mut phantoms: Vec<PhantomDependency> = Vec::new();
+    for path in paths {
+        let declared_elsewhere = |name: &str| {
+            let values = dependencies
+                .get(name)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            !values.iter().any(|v| &v.path == path) && values.iter().any(|v| &v.path != path)
+        };
+        let mut reported: BTreeSet<String> = BTreeSet::new();
+        for source in get_source_files(path) {
+            let Ok(content) = fs::read_to_string(&source) else {
+                continue;
+            };
+            for specifier in find_specifiers(&pattern, &content) {
+                let Some(name) = get_package_name(specifier) else {
+                    continue;
+                };
+                if declared_elsewhere(name) && reported.insert(name.to_string()) {
+                    phantoms.push(PhantomDependency {
+                        name: name.to_string(),
+                        path: path.clone(),
+                        source: source.clone(),
+                    });
+                }
+            }
+        }
+    }
+    phantoms.sort_by(|a, b| (&a.path, &a.name).cmp(&(&b.path, &b.name)));
+
+    phantoms
+}
+
+// `from '…'` covers `import … from` and `export … from`, string literals elsewhere are not imports
+fn import_pattern() -> Regex {
+    Regex::new(
+        r#"\bfrom\s*['"]([^'"\n]+)['"]|\bimport\s*['"]([^'"\n]+)['"]|\b(?:require|import)\s*\(\s*['"]([^'"\n]+)['"]\s*\)"#,
+    )
+    .unwrap()
+}
+
+fn find_specifiers<'a>(pattern: &Regex, content: &'a str) -> Vec<&'a str> {
+    pattern
+        .captures_iter(content)
+        .filter_map(|caps| caps.iter().skip(1).flatten().next())
+        .map(|m| m.as_str())
+        .collect()
+}
+
+// Sources of nested workspaces belong to them, so folders with their own package.json are skipped
+fn get_source_files(package_json: &Path) -> Vec<PathBuf> {
+    let root = package_json.parent().unwrap_or(Path::new("."));
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|e: &DirEntry| {
+            e.depth() == 0
+                || !(e.file_name() == "node_modules"
+                    || e.file_type().is_dir() && e.path().join("package.json").exists())
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            e.path()
+                .extension()
+                .is_some_and(|ext| SOURCE_EXTENSIONS.iter().any(|s| ext == *s))
+        })
+        .map(DirEntry::into_path)
+        .collect()
+}
+
+// `lodash/merge` comes from `lodash` and `@acme/ui/button` from `@acme/ui`, relative imports,
+// `node:` and other protocols, and Node.js builtins are not packages
+fn get_package_name(specifier: &str) -> Option<&str> {
+    if specifier.starts_with('.') || specifier.starts_with('/') || specifier.contains(':') {
+        return None;
+    }
+    let mut segments = specifier.splitn(3, '/');
+    let first = segments.next()?;
+    let name = if first.starts_with('@') {
+        let second = segments.next()?;
+        &specifier[..first.len() + 1 + second.len()]
+    } else {
+        first
+    };
+    if BUILTIN_MODULES.contains(&name) {
+        return None;
+    }
+
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Counters;
+
+    #[test]
+    fn it_should_find_imports_declared_only_by_siblings() {
+        let paths = vec![
+            PathBuf::from("./src/fixtures/phantom/a/package.json"),
+            PathBuf::from("./src/fixtures/phantom/b/package.json"),
+        ];
+        let dependencies =
+            crate::parser::collect_dependencies(&paths, &[], &mut Counters::default());
+
+        let phantoms = find_phantom_dependencies(&paths, &dependencies);
+
+        let source = PathBuf::from("./src/fixtures/phantom/b/src/app.ts");
+        assert_eq!(
+            phantoms,
+            vec![
+                PhantomDependency {
+                    name: "@acme/ui".to_string(),
+                    path: paths[1].clone(),
+                    source: source.clone(),
+                },
+                PhantomDependency {
+                    name: "lodash".to_string(),
+                    path: paths[1].clone(),
+                    source,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_get_package_name_of_specifier() {
+        assert_eq!(get_package_name("lodash/merge"), Some("lodash"));
+        assert_eq!(get_package_name("@acme/ui/button"), Some("@acme/ui"));
+        assert_eq!(get_package_name("@acme/ui"), Some("@acme/ui"));
+        assert_eq!(get_package_name("./helper"), None);
+        assert_eq!(get_package_name("node:path"), None);
+        assert_eq!(get_package_name("fs"), None);
+        assert_eq!(get_package_name("worker_threads"), None);
+        assert_eq!(get_package_name("fs/promises"), None);
+        assert_eq!(get_package_name("node:worker_threads"), None);
+        assert_eq!(get_package_name("node:test"), None);
+        assert_eq!(get_package_name("@broken"), None);
+    }
+
+    #[test]
+    fn it_should_find_import_specifiers() {
+        let content = r#"
+import React from 'react';
+import { merge } from "lodash/merge";
+import type { Props } from '@acme/ui';
+import 'polyfill';
+export { x } from 'reexported';
+export * from "everything";
+const a = require('required');
+const b = await import('dynamic');
+"#;
+
+        let specifiers = find_specifiers(&import_pattern(), content);
+
+        assert_eq!(
+            specifiers,
+            vec![
+                "react",
+                "lodash/merge",
+                "@acme/ui",
+                "polyfill",
+                "reexported",
+                "everything",
+                "required",
+                "dynamic",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_should_not_take_string_literals_for_imports() {
+        let content = r#"
+export const x = "lodash";
+export function f() { return 'react' }
+export default {
+  name: 'vue',
+};
+import {
+  a,
+} from 'multiline';
+const from = 'express';
+"#;
+
+        let specifiers = find_specifiers(&import_pattern(), content);
+
+        assert_eq!(specifiers, vec!["multiline"]);
+    }
+}
@@ -0,0 +1,113 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+};
+
+use crate::parser::{get_comparable_version, get_version_kind, PackageValue, VersionKind};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PolicyViolation {
+    pub name: String,
+    pub path: PathBuf,
+    pub range: String,
+    pub approved: String,
+}
+
+// The policy maps package names to their approved range, e.g. `{"react": "^18.2.0"}`
+pub fn read_policy(path: &str) -> BTreeMap<String, String> {
+    let content = fs::read_to_string(path).unwrap_or_else(|_| {
+        panic!("Failed to read the policy: {}", path);
+    });
+    serde_json::from_str(&content).unwrap_or_else(|_| {
+        panic!("Failed to parse the policy: {}", path);
+    })
+}
+
+// Ranges are compared the same way duplicates are, so `^18.2.0` complies with an approved `18.2.0`
+pub fn find_policy_violations(
+    dependencies: &HashMap<String, Vec<PackageValue>>,
+    policy: &BTreeMap<String, String>,
+) -> Vec<PolicyViolation> {
+    let mut violations: Vec<PolicyViolation> = policy
+        .iter()
+        .flat_map(|(name, approved)| {
+            let approved_version = get_comparable_version(approved, get_version_kind(approved));
+            dependencies
+                .get(name)
+                .into_iter()
+                .flatten()
+                .filter(move |v| {
+                    v.kind != VersionKind::NonRegistry && v.version != approved_version
+                })
+                .map(move |v| PolicyViolation {
+                    name: name.clone(),
+                    path: v.path.clone(),
+                    range: v.range.clone(),
+                    approved: approved.clone(),
+                })
+        })
+        .collect();
+    violations.sort_by(|a, b| (&a.name, &a.path).cmp(&(&b.name, &b.path)));
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependencies() -> HashMap<String, Vec<PackageValue>> {
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        hash_map.insert(
+            "react".to_string(),
+            vec![
+                PackageValue::new("react", "18.2.0", "./a").with_range("^18.2.0"),
+                PackageValue::new("react", "17.0.0", "./b").with_range("^17.0.0"),
+            ],
+        );
+        hash_map.insert(
+            "lodash".to_string(),
+            vec![PackageValue::new("lodash", "4.17.21", "./a").with_range("4.17.21")],
+        );
+        hash_map
+    }
+
+    #[test]
+    fn it_should_report_versions_violating_policy() {
+        let policy = BTreeMap::from([("react".to_string(), "18.2.0".to_string())]);
+
+        let violations = find_policy_violations(&dependencies(), &policy);
+
+        assert_eq!(
+            violations,
+            vec![PolicyViolation {
+                name: "react".to_string(),
+                path: PathBuf::from("./b"),
+                range: "^17.0.0".to_string(),
+                approved: "18.2.0".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn it_should_accept_compliant_versions() {
+        let policy = BTreeMap::from([
+            ("lodash".to_string(), "^4.17.21".to_string()),
+            ("vue".to_string(), "^3.0.0".to_string()),
+        ]);
+
+        assert!(find_policy_violations(&dependencies(), &policy).is_empty());
+    }
+
+    #[test]
+    fn it_should_read_policy_file() {
+        let path = std::env::temp_dir().join("nodedup-policy-test.json");
+        fs::write(&path, r#"{"react": "^18.2.0"}"#).unwrap();
+
+        let policy = read_policy(path.to_str().unwrap());
+
+        assert_eq!(policy["react"], "^18.2.0");
+        fs::remove_file(path).unwrap();
+    }
+}
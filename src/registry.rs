@@ -0,0 +1,189 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use semver::Version;
+
+use crate::{
+    compat::to_version_req,
+    npmrc::Registries,
+    parser::{normalize_version, PackageValue, VersionKind},
+};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct UnpublishedVersion {
+    pub name: String,
+    pub range: String,
+}
+
+// Published versions of each package, None when the registry does not know the package
+#[derive(Default)]
+pub struct RegistryCache {
+    packages: HashMap<String, Option<HashSet<Version>>>,
+}
+
+impl RegistryCache {
+    fn get_versions(&mut self, name: &str, registries: &Registries) -> Option<&HashSet<Version>> {
+        self.packages
+            .entry(name.to_string())
+            .or_insert_with(|| fetch_versions(name, registries.resolve(name)))
+            .as_ref()
+    }
+}
+
+fn fetch_versions(name: &str, registry: &str) -> Option<HashSet<Version>> {
+    let url = format!(
+        "{}/{}",
+        registry.trim_end_matches('/'),
+        name.replace('/', "%2f")
+    );
+    let response = match ureq::get(&url).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(404, _)) => return None,
+        Err(e) => {
+            log::warn!("Skipping registry check of {}: {}", name, e);
+            return Some(HashSet::new());
+        }
+    };
+    let content = response.into_string().unwrap_or_else(|_| {
+        panic!("Failed to read the registry response: {}", url);
+    });
+    let packument: serde_json::Value = serde_json::from_str(&content).unwrap_or_else(|_| {
+        panic!("Failed to parse the registry response: {}", url);
+    });
+
+    Some(
+        packument
+            .get("versions")
+            .and_then(|versions| versions.as_object())
+            .map(|versions| {
+                versions
+                    .keys()
+                    .filter_map(|v| Version::parse(v).ok())
+                    .collect()
+            })
+            .unwrap_or_default(),
+    )
+}
+
+// A range counts as published when any published version satisfies it. Ranges the semver crate
+// cannot read, and packages the registry could not be asked about, are not reported
+pub fn find_unpublished_versions(
+    duplicates: &HashMap<String, Vec<PackageValue>>,
+    registries: &Registries,
+    cache: &mut RegistryCache,
+) -> Vec<UnpublishedVersion> {
+    let mut unpublished = Vec::new();
+    for (name, values) in duplicates {
+        let ranges: BTreeSet<&str> = values
+            .iter()
+            .filter(|v| v.kind != VersionKind::NonRegistry)
+            .map(|v| v.range.as_str())
+            .collect();
+        for range in ranges {
+            let Some(requirement) = to_version_req(&normalize_version(range)) else {
+                continue;
+            };
+            let published = match cache.get_versions(name, registries) {
+                Some(versions) if versions.is_empty() => continue,
+                Some(versions) => versions.iter().any(|v| requirement.matches(v)),
+                None => false,
+            };
+            if !published {
+                unpublished.push(UnpublishedVersion {
+                    name: name.clone(),
+                    range: range.to_string(),
+                });
+            }
+        }
+    }
+    unpublished.sort_by(|a, b| (&a.name, &a.range).cmp(&(&b.name, &b.range)));
+
+    unpublished
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::npmrc::parse_npmrc;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // Answers every request with the packument of lodash, except left-pad which is unknown
+    fn serve_mock_registry() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().map_while(Result::ok) {
+                let request_line = BufReader::new(&stream)
+                    .lines()
+                    .map_while(Result::ok)
+                    .take_while(|line| !line.is_empty())
+                    .collect::<Vec<String>>()
+                    .remove(0);
+                counter.fetch_add(1, Ordering::SeqCst);
+                let (status, body) = if request_line.contains("/left-pad ") {
+                    ("404 Not Found", "{}")
+                } else {
+                    ("200 OK", r#"{"versions": {"4.17.20": {}, "4.17.21": {}}}"#)
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    #[test]
+    fn it_should_report_versions_missing_from_registry() {
+        let (url, requests) = serve_mock_registry();
+        let registries = parse_npmrc(&format!("registry={}", url));
+        let mut duplicates: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        duplicates.insert(
+            "lodash".to_string(),
+            vec![
+                PackageValue::new("lodash", "4.17.21", "./a").with_range("^4.17.21"),
+                PackageValue::new("lodash", "4.17.99", "./b").with_range("4.17.99"),
+                PackageValue::new("lodash", "4.17.20", "./c").with_range("4.17.20"),
+            ],
+        );
+        duplicates.insert(
+            "left-pad".to_string(),
+            vec![
+                PackageValue::new("left-pad", "1.0.0", "./a").with_range("^1.0.0"),
+                PackageValue::new("left-pad", "2.0.0", "./b").with_range("^2.0.0"),
+            ],
+        );
+
+        let unpublished =
+            find_unpublished_versions(&duplicates, &registries, &mut RegistryCache::default());
+
+        assert_eq!(
+            unpublished,
+            vec![
+                UnpublishedVersion {
+                    name: "left-pad".to_string(),
+                    range: "^1.0.0".to_string(),
+                },
+                UnpublishedVersion {
+                    name: "left-pad".to_string(),
+                    range: "^2.0.0".to_string(),
+                },
+                UnpublishedVersion {
+                    name: "lodash".to_string(),
+                    range: "4.17.99".to_string(),
+                },
+            ]
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+}
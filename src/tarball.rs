@@ -0,0 +1,61 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use tar::Archive;
+
+// The archive is decompressed in memory, each package.json is returned with its path inside it
+pub fn read_package_json_entries(tarball: &str) -> Vec<(PathBuf, String)> {
+    let bytes = fs::read(tarball).unwrap_or_else(|e| {
+        panic!("Failed to read the tarball: {}: {}", tarball, e);
+    });
+    let mut archive = Archive::new(GzDecoder::new(bytes.as_slice()));
+    let entries = archive.entries().unwrap_or_else(|e| {
+        panic!("Failed to read the tarball: {}: {}", tarball, e);
+    });
+
+    let mut package_jsons: Vec<(PathBuf, String)> = Vec::new();
+    for entry in entries {
+        let mut entry = entry.unwrap_or_else(|e| {
+            panic!("Failed to read the tarball: {}: {}", tarball, e);
+        });
+        let path = Path::new(tarball).join(entry.path().unwrap_or_default());
+        if path.file_name().is_none_or(|n| n != "package.json") {
+            continue;
+        }
+        let mut content = String::new();
+        if entry.read_to_string(&mut content).is_err() {
+            log::warn!("Skipping {}: not valid UTF-8", path.display());
+            continue;
+        }
+        package_jsons.push((path, content));
+    }
+    package_jsons.sort();
+
+    package_jsons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_read_package_json_entries() {
+        let tarball = "./src/fixtures/tarball/widget-1.0.0.tgz";
+
+        let entries = read_package_json_entries(tarball);
+
+        let paths: Vec<&PathBuf> = entries.iter().map(|(path, _)| path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                &Path::new(tarball).join("package/node_modules/legacy-widget/package.json"),
+                &Path::new(tarball).join("package/package.json"),
+            ]
+        );
+        assert!(entries[1].1.contains("\"name\": \"widget\""));
+    }
+}
@@ -0,0 +1,119 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::parser::{get_versions, PackageValue, VersionKind};
+
+const TYPES_SCOPE: &str = "@types/";
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TypesMismatch {
+    pub name: String,
+    pub path: PathBuf,
+    pub version: String,
+    pub types_version: String,
+}
+
+// DefinitelyTyped publishes `@scope/name` typings as `@types/scope__name`
+fn get_typed_package(types_name: &str) -> Option<String> {
+    let name = types_name.strip_prefix(TYPES_SCOPE)?;
+    match name.split_once("__") {
+        Some((scope, name)) => Some(format!("@{}/{}", scope, name)),
+        None => Some(name.to_string()),
+    }
+}
+
+// Typings follow the major version of the package they describe, so only majors are compared
+pub fn find_types_mismatches(
+    dependencies: &HashMap<String, Vec<PackageValue>>,
+) -> Vec<TypesMismatch> {
+    let mut mismatches: Vec<TypesMismatch> = dependencies
+        .iter()
+        .filter_map(|(types_name, types)| {
+            let name = get_typed_package(types_name)?;
+            Some((name, types))
+        })
+        .flat_map(|(name, types)| {
+            let values = dependencies
+                .get(&name)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            types.iter().filter_map(move |types_value| {
+                let value = values.iter().find(|v| v.path == types_value.path)?;
+                if value.kind == VersionKind::NonRegistry
+                    || types_value.kind == VersionKind::NonRegistry
+                    || get_versions(&value.version).0 == get_versions(&types_value.version).0
+                {
+                    return None;
+                }
+                Some(TypesMismatch {
+                    name: name.clone(),
+                    path: value.path.clone(),
+                    version: value.version.clone(),
+                    types_version: types_value.version.clone(),
+                })
+            })
+        })
+        .collect();
+    mismatches.sort_by(|a, b| (&a.name, &a.path).cmp(&(&b.name, &b.path)));
+
+    mismatches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependencies(values: &[(&str, &str, &str)]) -> HashMap<String, Vec<PackageValue>> {
+        let mut hash_map: HashMap<String, Vec<PackageValue>> = HashMap::new();
+        for (name, version, path) in values {
+            hash_map
+                .entry(name.to_string())
+                .or_default()
+                .push(PackageValue::new(name, version, path));
+        }
+        hash_map
+    }
+
+    #[test]
+    fn it_should_report_misaligned_types() {
+        let dependencies = dependencies(&[
+            ("lodash", "4.17.0", "./a"),
+            ("@types/lodash", "3.10.0", "./a"),
+            ("react", "18.2.0", "./a"),
+            ("@types/react", "18.0.1", "./a"),
+        ]);
+
+        let mismatches = find_types_mismatches(&dependencies);
+
+        assert_eq!(
+            mismatches,
+            vec![TypesMismatch {
+                name: "lodash".to_string(),
+                path: PathBuf::from("./a"),
+                version: "4.17.0".to_string(),
+                types_version: "3.10.0".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn it_should_compare_within_the_same_file() {
+        let dependencies = dependencies(&[
+            ("lodash", "4.17.0", "./a"),
+            ("lodash", "3.0.0", "./b"),
+            ("@types/lodash", "4.14.0", "./a"),
+            ("@types/node", "20.0.0", "./b"),
+        ]);
+
+        assert!(find_types_mismatches(&dependencies).is_empty());
+    }
+
+    #[test]
+    fn it_should_map_scoped_typings() {
+        assert_eq!(
+            get_typed_package("@types/babel__core"),
+            Some("@babel/core".to_string())
+        );
+        assert_eq!(get_typed_package("@types/node"), Some("node".to_string()));
+        assert_eq!(get_typed_package("lodash"), None);
+    }
+}
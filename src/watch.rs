@@ -0,0 +1,116 @@
+use std::{
+    path::Path,
+    sync::mpsc::{channel, Receiver},
+    time::Duration,
+};
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::lookup;
+
+pub const CLEAR_SCREEN: &str = "\x1B[2J\x1B[1;1H";
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+pub fn watch(dir_path: &str, on_change: impl FnMut()) {
+    let (sender, receiver) = channel();
+    let mut watcher = notify::recommended_watcher(sender).unwrap_or_else(|e| {
+        panic!("Failed to start the file watcher: {}", e);
+    });
+    watcher
+        .watch(Path::new(dir_path), RecursiveMode::Recursive)
+        .unwrap_or_else(|e| {
+            panic!("Failed to watch the path: {}: {}", dir_path, e);
+        });
+
+    listen(&receiver, DEBOUNCE, on_change);
+}
+
+// Saving a file usually fires several events in a row, so the rescan waits until they stop coming
+fn listen(
+    receiver: &Receiver<notify::Result<Event>>,
+    debounce: Duration,
+    mut on_change: impl FnMut(),
+) {
+    while let Ok(event) = receiver.recv() {
+        if !event.is_ok_and(|e| is_manifest_change(&e)) {
+            continue;
+        }
+        while receiver.recv_timeout(debounce).is_ok() {}
+        on_change();
+    }
+}
+
+fn is_manifest_change(event: &Event) -> bool {
+    matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) && event.paths.iter().any(|p| {
+        lookup::is_manifest_name(p) && !p.components().any(|c| c.as_os_str() == "node_modules")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use notify::event::{CreateKind, ModifyKind};
+
+    use super::*;
+
+    fn event(kind: EventKind, path: &str) -> notify::Result<Event> {
+        Ok(Event::new(kind).add_path(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn it_should_rescan_once_for_burst_of_changes() {
+        let (sender, receiver) = channel();
+        sender
+            .send(event(EventKind::Modify(ModifyKind::Any), "a/package.json"))
+            .unwrap();
+        sender
+            .send(event(EventKind::Create(CreateKind::File), "b/package.json"))
+            .unwrap();
+        drop(sender);
+
+        let mut rescans = 0;
+        listen(&receiver, Duration::from_millis(10), || rescans += 1);
+
+        assert_eq!(rescans, 1);
+    }
+
+    #[test]
+    fn it_should_rescan_for_deno_manifests() {
+        for path in ["a/deno.json", "a/deno.jsonc"] {
+            let (sender, receiver) = channel();
+            sender
+                .send(event(EventKind::Modify(ModifyKind::Any), path))
+                .unwrap();
+            drop(sender);
+
+            let mut rescans = 0;
+            listen(&receiver, Duration::from_millis(10), || rescans += 1);
+
+            assert_eq!(rescans, 1);
+        }
+    }
+
+    #[test]
+    fn it_should_not_rescan_for_other_files() {
+        let (sender, receiver) = channel();
+        sender
+            .send(event(EventKind::Modify(ModifyKind::Any), "a/index.js"))
+            .unwrap();
+        sender
+            .send(event(
+                EventKind::Modify(ModifyKind::Any),
+                "node_modules/react/package.json",
+            ))
+            .unwrap();
+        drop(sender);
+
+        let mut rescans = 0;
+        listen(&receiver, Duration::from_millis(10), || rescans += 1);
+
+        assert_eq!(rescans, 0);
+    }
+}